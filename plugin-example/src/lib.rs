@@ -1,11 +1,13 @@
 use samp::amx::Amx;
-use samp::cell::{AmxCell, AmxString, Ref, UnsizedBuffer};
+use samp::cell::{AmxCell, AmxString, AmxStringOut, Ref};
 use samp::error::AmxResult;
 use samp::plugin::SampPlugin;
-use samp::{initialize_plugin, native};
+use samp::{initialize_plugin, native, natives};
 
 use log::{info, error, debug};
 
+use std::error::Error;
+
 use memcache::Client;
 
 #[derive(Debug, Clone, Copy)]
@@ -31,8 +33,9 @@ struct Memcached {
     clients: Vec<Client>,
 }
 
+#[natives(prefix = "Memcached_")]
 impl Memcached {
-    #[native(name = "Memcached_Connect")]
+    #[native]
     pub fn connect(&mut self, _: &Amx, address: AmxString) -> AmxResult<MemcacheResult> {
         match Client::connect(address.to_string()) {
             Ok(client) => {
@@ -43,7 +46,7 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_Get")]
+    #[native]
     pub fn get(
         &mut self, _: &Amx, con: usize, key: AmxString, mut value: Ref<i32>,
     ) -> AmxResult<MemcacheResult> {
@@ -61,16 +64,14 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_GetString")]
+    #[native]
     pub fn get_string(
-        &mut self, _: &Amx, con: usize, key: AmxString, buffer: UnsizedBuffer, size: usize,
+        &mut self, _: &Amx, con: usize, key: AmxString, dest: &mut AmxStringOut,
     ) -> AmxResult<MemcacheResult> {
         if con < self.clients.len() {
             match self.clients[con].get::<String>(&key.to_string()) {
                 Ok(Some(data)) => {
-                    let mut buffer = buffer.into_sized_buffer(size);
-                    let _ = samp::cell::string::put_in_buffer(&mut buffer, &data);
-
+                    dest.write_str(&data);
                     Ok(MemcacheResult::Success(1))
                 }
                 Ok(None) => Ok(MemcacheResult::NoData),
@@ -81,7 +82,7 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_Set")]
+    #[native]
     pub fn set(
         &mut self, _: &Amx, con: usize, key: AmxString, value: i32, expire: u32,
     ) -> AmxResult<MemcacheResult> {
@@ -95,7 +96,7 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_SetString")]
+    #[native]
     pub fn set_string(
         &mut self, _: &Amx, con: usize, key: AmxString, value: AmxString, expire: u32,
     ) -> AmxResult<MemcacheResult> {
@@ -109,7 +110,7 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_Increment")]
+    #[native]
     pub fn increment(
         &mut self, _: &Amx, con: usize, key: AmxString, value: i32,
     ) -> AmxResult<MemcacheResult> {
@@ -123,7 +124,7 @@ impl Memcached {
         }
     }
 
-    #[native(name = "Memcached_Delete")]
+    #[native]
     pub fn delete(&mut self, _: &Amx, con: usize, key: AmxString) -> AmxResult<MemcacheResult> {
         if con < self.clients.len() {
             match self.clients[con].delete(&key.to_string()) {
@@ -138,51 +139,42 @@ impl Memcached {
 }
 
 impl SampPlugin for Memcached {
-    fn on_load(&mut self) {
+    fn on_load(&mut self) -> Result<(), Box<dyn Error>> {
         info!("that's a info msg");
         error!("that's an error msg");
         debug!("testique");
+
+        Ok(())
     }
 }
 
-initialize_plugin!(
-    natives: [
-        Memcached::connect,
-        Memcached::get,
-        Memcached::set,
-        Memcached::get_string,
-        Memcached::set_string,
-        Memcached::increment,
-        Memcached::delete,
-    ],
-    {
-        samp::plugin::enable_process_tick();
-        samp::encoding::set_default_encoding(samp::encoding::WINDOWS_1251); // Cyrillic
-
-        // get a default samp logger (uses samp logprintf).
-        let samp_logger = samp::plugin::logger()
-            .level(log::LevelFilter::Info); // logging only info, warn and error messages
-
-        let log_file = fern::log_file("myplugin.log").expect("Something wrong!");
-
-        // log trace and debug messages in an another file
-        let trace_level = fern::Dispatch::new()
-            .level(log::LevelFilter::Trace)
-            .chain(log_file);
-
-        let _ = fern::Dispatch::new()
-            .format(|callback, message, record| {
-                // all messages will be formated like
-                // memcached error: something (error!("something"))
-                // memcached info: some info (info!("some info"))
-                callback.finish(format_args!("memcached {}: {}", record.level().to_string().to_lowercase(), message))
-            })
-            .chain(samp_logger)
-            .chain(trace_level)
-            .apply();
-        
-        return Memcached {
-            clients: Vec::new(),
-        };
-    }
-);
+initialize_plugin!({
+    samp::plugin::enable_process_tick();
+    samp::encoding::set_default_encoding(samp::encoding::WINDOWS_1251); // Cyrillic
+
+    // get a default samp logger (uses samp logprintf).
+    let samp_logger = samp::plugin::logger()
+        .level(log::LevelFilter::Info); // logging only info, warn and error messages
+
+    let log_file = fern::log_file("myplugin.log").expect("Something wrong!");
+
+    // log trace and debug messages in an another file
+    let trace_level = fern::Dispatch::new()
+        .level(log::LevelFilter::Trace)
+        .chain(log_file);
+
+    let _ = fern::Dispatch::new()
+        .format(|callback, message, record| {
+            // all messages will be formated like
+            // memcached error: something (error!("something"))
+            // memcached info: some info (info!("some info"))
+            callback.finish(format_args!("memcached {}: {}", record.level().to_string().to_lowercase(), message))
+        })
+        .chain(samp_logger)
+        .chain(trace_level)
+        .apply();
+    
+    return Memcached {
+        clients: Vec::new(),
+    };
+});
@@ -4,18 +4,58 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 
 mod native;
+mod natives;
 mod plugin;
 
 pub(crate) const NATIVE_PREFIX: &str = "__samp_native_";
 pub(crate) const REG_PREFIX: &str = "__samp_reg_";
+pub(crate) const DEPRECATED_PREFIX: &str = "__samp_deprecated_";
+// passed by `#[natives]` to every `#[native]` it registers
+pub(crate) const NATIVES_FLAG: &str = "__samp_natives";
 
 /// Generate C function that parses passed argument and calls current function.
+///
+/// Supported arguments: `name = "..."`, `raw`, and repeatable `alias = "..."` and `deprecated = "..."`.
+/// Aliases and deprecated names are registered by `#[natives]`, a deprecated name logs a warning when it's called first time.
+///
+/// Trailing arguments can be optional: an `Option<T>` argument is `None` and a `#[default = value]` argument is `value`
+/// when a script passes less arguments (`native Foo(a, b = 5);` called by an old include).
+///
+/// A `dest: &mut AmxStringOut` argument takes a `dest[], len` pair of arguments, `#[packed] dest: &mut AmxStringOut`
+/// writes packed strings.
+///
+/// A slice argument (`&[i32]`, `&mut [i32]`, `&[f32]` or `&mut [f32]`) takes its length from a following argument
+/// named by `#[len = "size"]`, the length is checked against the AMX data before the slice is made.
+///
+/// A native borrows the plugin while it runs. A `&mut self` native can't be re-entered (e.g. it calls a public
+/// that calls the same plugin), a nested call is logged and fails with `AmxError::Native` raised on the script.
+/// Natives taking `&self` can be nested, they keep their state in `Cell` or `RefCell` fields.
+///
+/// A `#[native]` is registered either by `#[natives]` on its impl block or by a `natives: [...]` list
+/// passed to `initialize_plugin!`, otherwise the plugin doesn't compile.
 #[proc_macro_attribute]
 pub fn native(args: TokenStream, input: TokenStream) -> TokenStream {
     native::create_native(args, input)
 }
 
+/// Registers all `#[native]` methods of an impl block in the plugin.
+///
+/// Natives registered this way are picked up by `initialize_plugin!` when it's called without a `natives` list.
+///
+/// A native without a name gets it from a method name in PascalCase after an optional `prefix`:
+/// `#[natives(prefix = "Memcached_")]` registers `fn get_string` as `Memcached_GetString`.
+///
+/// A name (including aliases and deprecated names) registered twice in a module fails the build,
+/// a duplicate in another module is logged when the plugin is loaded and makes `Load` fail.
+#[proc_macro_attribute]
+pub fn natives(args: TokenStream, input: TokenStream) -> TokenStream {
+    natives::create_natives(args, input)
+}
+
 /// Generates common plugin C interface.
+///
+/// The block returns a plugin or a `Result` with it, an error is logged when the server loads the plugin
+/// and makes `Load` fail, like an error returned by `SampPlugin::on_load`.
 #[proc_macro]
 pub fn initialize_plugin(input: TokenStream) -> TokenStream {
     plugin::create_plugin(input)
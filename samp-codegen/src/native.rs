@@ -1,22 +1,43 @@
 use proc_macro::TokenStream;
 use quote::{quote, quote_spanned};
 
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::Span;
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Error, FnArg, Ident, ItemFn, LitStr, Pat, Result, Token};
+use syn::{
+    parse_macro_input, Attribute, Error, Expr, FnArg, Ident, ItemFn, LitStr, Pat, Result, Token,
+    Type,
+};
 
+use crate::DEPRECATED_PREFIX;
+use crate::NATIVES_FLAG;
 use crate::NATIVE_PREFIX;
 use crate::REG_PREFIX;
 
-struct NativeName {
+#[derive(Default)]
+pub(crate) struct NativeName {
     pub name: String,
     pub raw: bool,
+    pub aliases: Vec<String>,
+    pub deprecated: Vec<String>,
+    // registered by `#[natives]`, not by a `natives: [...]` list
+    pub in_natives: bool,
+}
+
+impl NativeName {
+    /// Parse arguments of a `#[native(...)]` attribute found on a method.
+    pub(crate) fn from_attr(attr: &Attribute) -> Result<NativeName> {
+        if attr.tokens.is_empty() {
+            return Ok(NativeName::default());
+        }
+
+        attr.parse_args()
+    }
 }
 
 impl Parse for NativeName {
     fn parse(input: ParseStream) -> Result<Self> {
-        let mut name = String::new();
-        let mut raw = false;
+        let mut native = NativeName::default();
 
         while !input.is_empty() {
             let ident: Ident = input.parse()?;
@@ -25,138 +46,542 @@ impl Parse for NativeName {
                 let _: Token![=] = input.parse()?;
                 let native_name: LitStr = input.parse()?;
 
-                name = native_name.value();
+                native.name = native_name.value();
             } else if ident == "raw" {
-                raw = true;
+                native.raw = true;
+            } else if ident == "alias" {
+                let _: Token![=] = input.parse()?;
+                let alias: LitStr = input.parse()?;
+
+                native.aliases.push(alias.value());
+            } else if ident == "deprecated" {
+                let _: Token![=] = input.parse()?;
+                let deprecated: LitStr = input.parse()?;
+
+                native.deprecated.push(deprecated.value());
+            } else if ident == NATIVES_FLAG {
+                native.in_natives = true;
             } else {
                 return Err(Error::new(
                     ident.span(),
-                    "Unexpected argument name. Currently supports only \"name\" and \"raw\".",
+                    "Unexpected argument name. Currently supports only \"name\", \"raw\", \"alias\" and \"deprecated\".",
                 ));
             }
 
             let _: Option<Token![,]> = input.parse()?;
         }
 
-        Ok(NativeName { name, raw })
+        Ok(native)
     }
 }
 
+/// An argument of a native function that's parsed from `Args`.
+struct NativeArg {
+    ident: Ident,
+    kind: ArgKind,
+    is_option: bool,
+    // fallback value when a script doesn't pass trailing arguments
+    default: Option<proc_macro2::TokenStream>,
+    span: Span,
+}
+
+enum ArgKind {
+    /// Any `AmxCell` value.
+    Cell,
+    /// `&mut AmxStringOut` made from a `dest[]` and `len` pair of arguments.
+    StringOut { packed: bool },
+    /// `&[T]` or `&mut [T]` with a length passed in a following argument (`#[len = "size"]`).
+    Slice {
+        len: Ident,
+        mutable: bool,
+        elem: Box<Type>,
+    },
+}
+
+impl ArgKind {
+    /// Count of AMX arguments used by an argument.
+    fn cells(&self) -> usize {
+        match self {
+            ArgKind::Cell | ArgKind::Slice { .. } => 1,
+            ArgKind::StringOut { .. } => 2,
+        }
+    }
+}
+
+/// Attributes of native arguments that are removed from an origin function.
+const ARG_ATTRS: &[&str] = &["default", "packed", "len"];
+
+/// Element types of slices that have a size of an AMX cell.
+const SLICE_TYPES: &[&str] = &["i32", "u32", "f32"];
+
 // TODO: Allow use with functions that's not methods
 pub fn create_native(args: TokenStream, input: TokenStream) -> TokenStream {
     let native = parse_macro_input!(args as NativeName);
-    let origin_fn = parse_macro_input!(input as ItemFn);
+    let mut origin_fn = parse_macro_input!(input as ItemFn);
+
+    let native_args = if !native.raw {
+        match native_args(&mut origin_fn) {
+            Ok(native_args) => native_args,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        Vec::new()
+    };
 
     let vis = &origin_fn.vis;
-    let origin_name = &origin_fn.ident;
-    let args = origin_fn.decl.inputs.iter();
-    let native_name = prepend(&origin_fn.ident, NATIVE_PREFIX);
-    let reg_name = prepend(&origin_fn.ident, REG_PREFIX);
-    let amx_name = &native.name;
-
-    let fn_input = origin_fn.decl.inputs.iter().skip(2);
-
-    let fn_input = fn_input
-        .map(|arg| match arg {
-            FnArg::Captured(capt) => {
-                let pat = &capt.pat;
-
-                if let Pat::Ident(pat_ident) = pat {
-                    let ident = &pat_ident.ident;
-                    Some(quote_spanned!(capt.span() => #ident))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        })
-        .flatten();
-
-    let args_parsing: proc_macro2::TokenStream = if !native.raw {
-        args.skip(2).map(|arg| {
-            match arg {
-                FnArg::Captured(capt) => {
-                    let pat = &capt.pat;
-
-                    if let Pat::Ident(pat_ident) = pat {
-                        let ident = &pat_ident.ident;
-                        Some(quote_spanned!{
-                            capt.span() => 
-                                let #ident = match args.next() {
-                                    Some(#ident) => #ident,
-                                    None => {
-                                        println!("error: couldn't parse variable {:?} in {:?} function.", stringify!(#ident), #amx_name);
-                                        return 0;
-                                    }
-                                };
-                        })
-                    } else {
-                        None
-                    }
+    let origin_name = &origin_fn.sig.ident;
+    let native_name = prepend(origin_name, NATIVE_PREFIX);
+    let reg_name = prepend(origin_name, REG_PREFIX);
+    let amx_name = if native.name.is_empty() {
+        pascal_case(&origin_name.to_string())
+    } else {
+        native.name.clone()
+    };
+
+    let fn_input = native_args.iter().map(|arg| {
+        let ident = &arg.ident;
+
+        match arg.kind {
+            ArgKind::Cell | ArgKind::Slice { .. } => quote!(#ident),
+            ArgKind::StringOut { .. } => quote!(&mut #ident),
+        }
+    });
+
+    let mut position = 0;
+
+    let args_parsing: Vec<_> = native_args
+        .iter()
+        .map(|arg| {
+            let ident = &arg.ident;
+            let parsing = arg_parsing(arg, &amx_name);
+            let first_cell = position;
+
+            position += arg.kind.cells();
+
+            match (&arg.default, &arg.kind) {
+                (Some(default), _) => quote_spanned! {
+                    arg.span =>
+                        let #ident = if args.count() > #first_cell {
+                            #parsing
+                        } else {
+                            #default
+                        };
+                },
+                (None, ArgKind::StringOut { .. }) => quote_spanned! {
+                    arg.span =>
+                        let mut #ident = #parsing;
+                },
+                (None, ArgKind::Cell) => quote_spanned! {
+                    arg.span =>
+                        let #ident = #parsing;
+                },
+                (None, ArgKind::Slice { elem, .. }) => quote_spanned! {
+                    arg.span =>
+                        let #ident: samp::cell::UnsizedBuffer<#elem> = #parsing;
                 },
-                _ => None,
             }
-        }).flatten().collect()
+        })
+        .collect();
+
+    // slices are made when their lengths are parsed
+    let slices = native_args.iter().filter_map(|arg| match &arg.kind {
+        ArgKind::Slice { len, mutable, elem } => {
+            let ident = &arg.ident;
+
+            let (binding, slice) = if *mutable {
+                (quote!(mut #ident), quote!(&mut [#elem] = &mut #ident))
+            } else {
+                (quote!(#ident), quote!(&[#elem] = &#ident))
+            };
+
+            Some(quote_spanned! {
+                arg.span =>
+                    let #binding = {
+                        let len = samp::cell::AmxCell::as_cell(&#len);
+
+                        if len < 0 {
+                            samp::plugin::native_error(format_args!("array {:?} in {:?} function has a negative length {}.", stringify!(#ident), #amx_name, len));
+                            return 0;
+                        }
+
+                        let len = len as usize;
+
+                        match #ident.into_sized_buffer(len) {
+                            Ok(buffer) => buffer,
+                            Err(err) => {
+                                samp::plugin::native_error(format_args!("array {:?} of length {} in {:?} function: {}.", stringify!(#ident), len, #amx_name, err));
+                                return 0;
+                            }
+                        }
+                    };
+
+                    let #ident: #slice;
+            })
+        }
+        _ => None,
+    });
+
+    // a native that takes `&self` shares the plugin with nested natives, `&mut self` borrows it exclusively
+    let shared = match origin_fn.sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => receiver.reference.is_some() && receiver.mutability.is_none(),
+        _ => false,
+    };
+
+    let (plugin_binding, get_plugin) = if shared {
+        (quote!(plugin), quote!(samp::plugin::get_ref::<Self>))
     } else {
-        proc_macro2::TokenStream::new()
+        (quote!(mut plugin), quote!(samp::plugin::get::<Self>))
     };
 
     let call_origin = if !native.raw {
-        quote!(plugin.as_mut().#origin_name(amx, #(#fn_input),*))
+        quote!(plugin.#origin_name(amx, #(#fn_input),*))
     } else {
-        quote!(plugin.as_mut().#origin_name(amx, args))
+        quote!(plugin.#origin_name(amx, args))
     };
 
     let native_generated = quote! {
         #vis extern "C" fn #native_name(amx: *mut samp::raw::types::AMX, args: *mut i32) -> i32 {
             let amx_ident = samp::amx::AmxIdent::from(amx);
 
+            // `None` only when it's called not from the server thread
             let amx = match samp::amx::get(amx_ident) {
                 Some(amx) => amx,
-                None => {
-                    samp::amx::add(amx);  // For GDK
-                    samp::amx::get(amx_ident).unwrap()
-                }
+                None => match samp::amx::add(amx) { // For GDK
+                    Some(amx) => amx,
+                    None => return 0,
+                },
+            };
+
+            // fails on re-entrancy, the AMX gets an error then
+            let #plugin_binding = match #get_plugin(#amx_name, amx) {
+                Some(plugin) => plugin,
+                None => return 0,
             };
 
             let mut args = samp::args::Args::new(amx, args);
-            let mut plugin = samp::plugin::get::<Self>();
+            let _span = samp::plugin::native_span(#amx_name, amx, &args);
 
             #(#args_parsing)*
+            #(#slices)*
 
-            unsafe {
-                match #call_origin {
-                    Ok(retval) => {
-                        return samp::plugin::convert_return_value(retval);
-                    },
+            match #call_origin {
+                Ok(retval) => {
+                    return samp::plugin::convert_return_value(retval);
+                },
 
-                    Err(err) => {
-                        println!("error: {}", err);
-                        return 0;
-                    }
+                Err(err) => {
+                    samp::plugin::native_error(err);
+                    return 0;
                 }
             }
         }
     };
 
+    // a static nul-terminated name, so nothing is allocated every `AmxLoad`
+    let reg_amx_name = format!("{}\0", amx_name);
+
+    // a native that's not under `#[natives]` is registered only by a list passed to `initialize_plugin!`
+    let listed_check = if native.in_natives {
+        quote!()
+    } else {
+        quote_spanned!(origin_name.span()=> samp::plugin::listed_natives::<Self>();)
+    };
+
     let reg_native = quote! {
         #vis fn #reg_name() -> samp::raw::types::AMX_NATIVE_INFO {
+            #listed_check
+
             samp::raw::types::AMX_NATIVE_INFO {
-                name: std::ffi::CString::new(#amx_name).unwrap().into_raw(),
+                name: #reg_amx_name.as_ptr() as *const std::os::raw::c_char,
                 func: Self::#native_name,
             }
         }
     };
 
+    let deprecated_natives = native.deprecated.iter().enumerate().map(|(idx, old_name)| {
+        let deprecated_name = deprecated_ident(origin_name, idx);
+
+        quote! {
+            #vis extern "C" fn #deprecated_name(amx: *mut samp::raw::types::AMX, args: *mut i32) -> i32 {
+                static WARNING: std::sync::Once = std::sync::Once::new();
+
+                WARNING.call_once(|| samp::plugin::warn_deprecated(#old_name, #amx_name));
+                Self::#native_name(amx, args)
+            }
+        }
+    });
+
     let generated = quote! {
         #origin_fn
         #reg_native
         #native_generated
+        #(#deprecated_natives)*
     };
 
     generated.into()
 }
 
-fn prepend(ident: &Ident, prefix: &str) -> Ident {
+/// Generate an expression that reads an argument from `Args`.
+fn arg_parsing(arg: &NativeArg, amx_name: &str) -> proc_macro2::TokenStream {
+    let ident = &arg.ident;
+
+    let parse_next = |value: proc_macro2::TokenStream| {
+        quote_spanned! {
+            arg.span =>
+                match args.next() {
+                    Some(value) => #value,
+                    None => {
+                        samp::plugin::native_error(format_args!("couldn't parse variable {:?} in {:?} function.", stringify!(#ident), #amx_name));
+                        return 0;
+                    }
+                }
+        }
+    };
+
+    match arg.kind {
+        ArgKind::Cell if arg.is_option => parse_next(quote!(Some(value))),
+        ArgKind::Cell | ArgKind::Slice { .. } => parse_next(quote!(value)),
+        ArgKind::StringOut { packed } => {
+            let buffer = parse_next(quote!(value));
+            let len = parse_next(quote!(value));
+
+            quote_spanned! {
+                arg.span => {
+                    let buffer: samp::cell::UnsizedBuffer = #buffer;
+                    let len: usize = #len;
+
+                    match buffer.into_sized_buffer(len) {
+                        Ok(buffer) => samp::cell::AmxStringOut::new(buffer, #packed),
+                        Err(err) => {
+                            samp::plugin::native_error(format_args!("string {:?} of length {} in {:?} function: {}.", stringify!(#ident), len, #amx_name, err));
+                            return 0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collect arguments passed from an AMX, an `Option<T>` or a `#[default = ...]` argument can be omitted by a script.
+///
+/// `#[default]` attributes are removed from the function, because they're not real attributes.
+fn native_args(origin_fn: &mut ItemFn) -> Result<Vec<NativeArg>> {
+    let mut native_args = Vec::new();
+    let mut optional_span = None;
+
+    // skip `&mut self` and `&Amx`
+    for arg in origin_fn.sig.inputs.iter_mut().skip(2) {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(receiver.span(), "Unexpected receiver."));
+            }
+        };
+
+        let ident = match arg.pat.as_ref() {
+            Pat::Ident(pat_ident) => pat_ident.ident.clone(),
+            pat => {
+                return Err(Error::new(
+                    pat.span(),
+                    "Arguments of a native should be plain identifiers.",
+                ))
+            }
+        };
+
+        let mut default = None;
+        let mut packed = false;
+        let mut len = None;
+
+        for attr in &arg.attrs {
+            if attr.path.is_ident("default") {
+                let parser = |input: ParseStream| {
+                    let _: Token![=] = input.parse()?;
+                    input.parse::<Expr>()
+                };
+
+                let value = parser.parse2(attr.tokens.clone())?;
+                default = Some(quote!(#value));
+            } else if attr.path.is_ident("packed") {
+                packed = true;
+            } else if attr.path.is_ident("len") {
+                let parser = |input: ParseStream| {
+                    let _: Token![=] = input.parse()?;
+                    input.parse::<LitStr>()
+                };
+
+                let len_arg = parser.parse2(attr.tokens.clone())?;
+                len = Some(len_arg.parse::<Ident>()?);
+            }
+        }
+
+        arg.attrs
+            .retain(|attr| !ARG_ATTRS.iter().any(|name| attr.path.is_ident(name)));
+
+        let span = arg.span();
+        let is_option = is_option(&arg.ty);
+
+        let kind = if is_mut_ref_to(&arg.ty, "AmxStringOut") {
+            if default.is_some() || is_option {
+                return Err(Error::new(span, "An output string can't be optional."));
+            }
+
+            ArgKind::StringOut { packed }
+        } else if packed {
+            return Err(Error::new(
+                span,
+                "#[packed] can be used only with &mut AmxStringOut.",
+            ));
+        } else if let Some(len) = len {
+            let (mutable, elem) = slice_type(&arg.ty).ok_or_else(|| {
+                Error::new(
+                    span,
+                    "#[len] can be used only with &[T] or &mut [T] (T is i32, u32 or f32).",
+                )
+            })?;
+
+            if default.is_some() || is_option {
+                return Err(Error::new(span, "A slice can't be optional."));
+            }
+
+            ArgKind::Slice { len, mutable, elem }
+        } else {
+            ArgKind::Cell
+        };
+
+        if default.is_none() && is_option {
+            default = Some(quote!(None));
+        }
+
+        match (&default, optional_span) {
+            (Some(_), None) => optional_span = Some(span),
+            (None, Some(_)) => {
+                return Err(Error::new(
+                    span,
+                    "A required argument can't follow an optional one (Option<T> or #[default]).",
+                ));
+            }
+            _ => (),
+        }
+
+        native_args.push(NativeArg {
+            ident,
+            kind,
+            is_option,
+            default,
+            span,
+        });
+    }
+
+    check_lengths(&native_args)?;
+
+    Ok(native_args)
+}
+
+/// Check that lengths of slices are passed in following required arguments.
+fn check_lengths(native_args: &[NativeArg]) -> Result<()> {
+    for (idx, arg) in native_args.iter().enumerate() {
+        if let ArgKind::Slice { len, .. } = &arg.kind {
+            let len_arg = native_args[idx + 1..]
+                .iter()
+                .find(|len_arg| len_arg.ident == *len)
+                .ok_or_else(|| {
+                    Error::new(
+                        len.span(),
+                        format!(
+                            "There is no argument {:?} after the slice.",
+                            len.to_string()
+                        ),
+                    )
+                })?;
+
+            match len_arg.kind {
+                ArgKind::Cell if !len_arg.is_option => (),
+                _ => {
+                    return Err(Error::new(
+                        len_arg.span,
+                        "A length of a slice should be a required integer argument.",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns mutability and an element type of `&[T]` or `&mut [T]`.
+fn slice_type(ty: &Type) -> Option<(bool, Box<Type>)> {
+    match ty {
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Slice(slice) => match slice.elem.as_ref() {
+                Type::Path(type_path)
+                    if SLICE_TYPES.iter().any(|name| type_path.path.is_ident(name)) =>
+                {
+                    Some((reference.mutability.is_some(), slice.elem.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check that a type is `&mut Name` or `&mut path::to::Name<'_>`.
+fn is_mut_ref_to(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_some() => {
+            match reference.elem.as_ref() {
+                Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident == name)
+                    .unwrap_or(false),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+pub(crate) fn prepend(ident: &Ident, prefix: &str) -> Ident {
     Ident::new(&format!("{}{}", prefix, ident), ident.span())
 }
+
+/// Name of a wrapper that warns about a deprecated name of a native.
+pub(crate) fn deprecated_ident(ident: &Ident, idx: usize) -> Ident {
+    Ident::new(
+        &format!("{}{}_{}", DEPRECATED_PREFIX, ident, idx),
+        ident.span(),
+    )
+}
+
+/// Convert a snake_case name of a method into a PascalCase name of a native (`get_string` -> `GetString`).
+pub(crate) fn pascal_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
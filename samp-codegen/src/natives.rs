@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, Group, Span, TokenTree};
+use quote::quote;
+
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Error, Ident, ImplItem, ItemImpl, LitStr, Result, Token};
+
+use crate::native::{deprecated_ident, pascal_case, prepend, NativeName};
+use crate::{NATIVES_FLAG, NATIVE_PREFIX};
+
+const ENTRY_PREFIX: &str = "__SAMP_NATIVE_";
+
+struct NativesArgs {
+    prefix: String,
+}
+
+impl Parse for NativesArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut prefix = String::new();
+
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+
+            if ident == "prefix" {
+                let _: Token![=] = input.parse()?;
+                let native_prefix: LitStr = input.parse()?;
+
+                prefix = native_prefix.value();
+            } else {
+                return Err(Error::new(
+                    ident.span(),
+                    "Unexpected argument name. Currently supports only \"prefix\".",
+                ));
+            }
+
+            let _: Option<Token![,]> = input.parse()?;
+        }
+
+        Ok(NativesArgs { prefix })
+    }
+}
+
+/// A name a native is registered with.
+enum EntryKind {
+    Name,
+    Alias,
+    Deprecated(usize),
+}
+
+struct Entry {
+    method: Ident,
+    amx_name: String,
+    kind: EntryKind,
+    span: Span,
+}
+
+pub fn create_natives(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as NativesArgs);
+    let mut item_impl = parse_macro_input!(input as ItemImpl);
+
+    match registrations(&args, &mut item_impl) {
+        Ok(registrations) => {
+            let generated = quote! {
+                #item_impl
+                #registrations
+            };
+
+            generated.into()
+        }
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn registrations(args: &NativesArgs, item_impl: &mut ItemImpl) -> Result<proc_macro2::TokenStream> {
+    if !item_impl.generics.params.is_empty() {
+        return Err(Error::new(
+            item_impl.generics.span(),
+            "#[natives] can't be used on generic impl blocks.",
+        ));
+    }
+
+    let entries = native_entries(args, item_impl)?;
+    let mut names: HashMap<&str, Span> = HashMap::new();
+
+    for entry in &entries {
+        if names.insert(&entry.amx_name, entry.span).is_some() {
+            return Err(Error::new(
+                entry.span,
+                format!("Native {:?} is defined more than once.", entry.amx_name),
+            ));
+        }
+    }
+
+    let self_ty = &item_impl.self_ty;
+
+    let entries = entries.iter().map(|entry| {
+        let native_name = match entry.kind {
+            EntryKind::Name | EntryKind::Alias => prepend(&entry.method, NATIVE_PREFIX),
+            EntryKind::Deprecated(idx) => deprecated_ident(&entry.method, idx),
+        };
+
+        let entry_name = entry_ident(&entry.amx_name, entry.span);
+        let amx_name = format!("{}\0", entry.amx_name);
+
+        quote! {
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            #[samp::linkme::distributed_slice(samp::plugin::NATIVES)]
+            #[linkme(crate = samp::linkme)]
+            static #entry_name: samp::plugin::NativeEntry =
+                samp::plugin::NativeEntry::new(#amx_name, <#self_ty>::#native_name);
+        }
+    });
+
+    Ok(quote!(#(#entries)*))
+}
+
+fn native_entries(args: &NativesArgs, item_impl: &mut ItemImpl) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            let ident = method.sig.ident.clone();
+
+            for attr in method.attrs.iter_mut().filter(|attr| is_native_attr(attr)) {
+                let native = NativeName::from_attr(attr)?;
+                let span = attr.span();
+
+                // a name passed to `#[native]` overrides a generated one
+                let amx_name = if native.name.is_empty() {
+                    let amx_name = format!("{}{}", args.prefix, pascal_case(&ident.to_string()));
+                    set_args(attr, Some(&amx_name));
+                    amx_name
+                } else {
+                    set_args(attr, None);
+                    native.name
+                };
+
+                entries.push(Entry {
+                    method: ident.clone(),
+                    amx_name,
+                    kind: EntryKind::Name,
+                    span,
+                });
+
+                for alias in native.aliases {
+                    entries.push(Entry {
+                        method: ident.clone(),
+                        amx_name: alias,
+                        kind: EntryKind::Alias,
+                        span,
+                    });
+                }
+
+                for (idx, deprecated) in native.deprecated.into_iter().enumerate() {
+                    entries.push(Entry {
+                        method: ident.clone(),
+                        amx_name: deprecated,
+                        kind: EntryKind::Deprecated(idx),
+                        span,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn is_native_attr(attr: &Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map(|segment| segment.ident == "native")
+        .unwrap_or(false)
+}
+
+// pass a generated name to `#[native]`, so it registers the same name as `#[natives]`,
+// and tell it that the native is registered here
+fn set_args(attr: &mut Attribute, amx_name: Option<&str>) {
+    let flag = Ident::new(NATIVES_FLAG, Span::call_site());
+    let mut args = match amx_name {
+        Some(amx_name) => quote!(name = #amx_name, #flag),
+        None => quote!(#flag),
+    };
+
+    if let Some(TokenTree::Group(group)) = attr.tokens.clone().into_iter().next() {
+        let rest = group.stream();
+        args = quote!(#args, #rest);
+    }
+
+    let group = Group::new(Delimiter::Parenthesis, args);
+    attr.tokens = quote!(#group);
+}
+
+// a static named after the hex encoded Pawn name turns duplicates in a module into a compile error,
+// ones in different modules are found when the plugin is loaded
+fn entry_ident(amx_name: &str, span: Span) -> Ident {
+    let name: String = amx_name.bytes().map(|byte| format!("{:02X}", byte)).collect();
+
+    Ident::new(&format!("{}{}", ENTRY_PREFIX, name), span)
+}
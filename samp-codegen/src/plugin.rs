@@ -44,26 +44,55 @@ pub fn create_plugin(input: TokenStream) -> TokenStream {
     let mut plugin = parse_macro_input!(input as InitPlugin);
     let block = &plugin.block;
 
-    let natives: proc_macro2::TokenStream = plugin
-        .natives_list
-        .iter_mut()
-        .flatten()
-        .map(|path| {
-            if let Some(mut last_part) = path.segments.last_mut() {
-                last_part.value_mut().ident = Ident::new(
-                    &format!("{}{}", REG_PREFIX, last_part.value().ident),
-                    last_part.value().ident.span(),
-                );
+    // without an explicit list natives are taken from ones registered by `#[natives]`
+    let (natives, listed) = match plugin.natives_list.as_mut() {
+        Some(natives_list) => {
+            // types of listed natives, so a `#[native]` outside of `#[natives]` knows it's registered
+            let mut types: Vec<Path> = Vec::new();
+
+            for path in natives_list.iter().filter(|path| path.segments.len() > 1) {
+                let ty = Path {
+                    leading_colon: path.leading_colon,
+                    segments: path.segments.iter().take(path.segments.len() - 1).cloned().collect(),
+                };
+
+                if !types.iter().any(|known| quote!(#known).to_string() == quote!(#ty).to_string()) {
+                    types.push(ty);
+                }
             }
-            quote!(#path(),)
-        })
-        .collect();
+
+            let natives: proc_macro2::TokenStream = natives_list
+                .iter_mut()
+                .map(|path| {
+                    if let Some(last_part) = path.segments.last_mut() {
+                        last_part.ident = Ident::new(
+                            &format!("{}{}", REG_PREFIX, last_part.ident),
+                            last_part.ident.span(),
+                        );
+                    }
+                    quote!(#path(),)
+                })
+                .collect();
+
+            let listed = quote! {
+                #(impl samp::plugin::ListedNatives for #types {})*
+            };
+
+            (quote!(vec![#natives]), listed)
+        }
+        None => (quote!(samp::plugin::natives()), quote!()),
+    };
 
     let generated = quote! {
+        #listed
+
         #[no_mangle]
         pub extern "system" fn Load(server_data: *const usize) -> i32 {
-            samp::interlayer::load(server_data);
-            return 1;
+            if samp::interlayer::load(server_data) {
+                return 1;
+            }
+
+            return 0;
         }
 
         #[no_mangle]
@@ -73,9 +102,7 @@ pub fn create_plugin(input: TokenStream) -> TokenStream {
 
         #[no_mangle]
         pub extern "system" fn AmxLoad(amx: *mut samp::raw::types::AMX) {
-            let natives = vec![#natives];
-
-            samp::interlayer::amx_load(amx, &natives);
+            samp::interlayer::amx_load(amx);
         }
 
         #[no_mangle]
@@ -89,7 +116,10 @@ pub fn create_plugin(input: TokenStream) -> TokenStream {
                 #(#block)*
             };
 
-            samp::plugin::initialize(constructor);
+            // the table is built once and registered in every loaded script
+            let natives = #natives;
+
+            samp::plugin::initialize(constructor, natives);
             samp::interlayer::supports()
         }
 
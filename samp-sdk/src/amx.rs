@@ -1,16 +1,19 @@
 //! Core Amx types.
-use crate::cell::{AmxCell, AmxPrimitive, AmxString, Buffer, Ref};
+use crate::cell::array::{fill_indirection, indirection_len};
+use crate::cell::{string, AmxCell, AmxPrimitive, AmxString, Array2D, ArrayND, Buffer, PackedBuffer, Ref};
 use crate::consts::{AmxExecIdx, AmxFlags};
 use crate::error::{AmxError, AmxResult};
 use crate::exports::*;
 use crate::raw::types::{AMX, AMX_HEADER, AMX_NATIVE_INFO};
 
 #[cfg(feature = "encoding")]
-use crate::encoding;
+use crate::encoding::{self, Encoding};
 
-use std::ffi::CString;
+#[cfg(feature = "encoding")]
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
 use std::ptr::NonNull;
-use std::borrow::Cow;
 
 macro_rules! amx_try {
     ($call:expr) => {
@@ -27,6 +30,8 @@ macro_rules! amx_try {
 pub struct Amx {
     ptr: *mut AMX,
     fn_table: usize,
+    #[cfg(feature = "encoding")]
+    encoding: Cell<Option<&'static Encoding>>,
 }
 
 impl Amx {
@@ -60,7 +65,42 @@ impl Amx {
     /// }
     /// ```
     pub fn new(ptr: *mut AMX, fn_table: usize) -> Amx {
-        Amx { ptr, fn_table }
+        Amx {
+            ptr,
+            fn_table,
+            #[cfg(feature = "encoding")]
+            encoding: Cell::new(None),
+        }
+    }
+
+    /// Set an encoding of strings of the AMX, `None` makes it use the default encoding.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::amx::Amx;
+    /// use samp_sdk::encoding;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: SetScriptEncoding(const label[]);
+    /// fn set_script_encoding(amx: &Amx, label: &str) -> AmxResult<bool> {
+    ///     match encoding::for_label(label) {
+    ///         Some(encoding) => {
+    ///             amx.set_encoding(Some(encoding));
+    ///             Ok(true)
+    ///         }
+    ///         None => Ok(false),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "encoding")]
+    pub fn set_encoding(&self, encoding: Option<&'static Encoding>) {
+        self.encoding.set(encoding);
+    }
+
+    /// Return an encoding of strings of the AMX.
+    #[cfg(feature = "encoding")]
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding.get().unwrap_or_else(encoding::default_encoding)
     }
 
     /// Register a list of plugin natives functions.
@@ -70,7 +110,7 @@ impl Amx {
     /// use samp_sdk::amx::Amx;
     /// use samp_sdk::raw::types::{AMX, AMX_NATIVE_INFO};
     ///
-    /// use std::ffi::CString;
+    /// use std::ffi::{CStr, CString};
     ///
     /// #[no_mangle]
     /// pub extern "system" fn AmxLoad(amx_ptr: *mut AMX) {
@@ -102,7 +142,7 @@ impl Amx {
         Ok(())
     }
 
-    pub(crate) fn allot<T: Sized + AmxPrimitive>(&self, cells: usize) -> AmxResult<Ref<T>> {
+    pub(crate) fn allot<T: Sized + AmxPrimitive>(&self, cells: usize) -> AmxResult<Ref<'_, T>> {
         let allot = Allot::from_table(self.fn_table);
 
         let mut amx_addr = 0;
@@ -110,13 +150,15 @@ impl Amx {
 
         amx_try!(allot(self.ptr, cells as i32, &mut amx_addr, &mut phys_addr));
 
-        unsafe { Ok(Ref::new(amx_addr, phys_addr as *mut T)) }
+        unsafe { Ok(Ref::new(self, amx_addr, phys_addr as *mut T)) }
     }
 
     // TODO: return any type that can be converted to an amx cell
 
     /// Execs an AMX function.
     ///
+    /// With the `tracing` feature the call is made inside of an `exec` span with the AMX and the index.
+    ///
     /// # Examples
     ///
     /// ```
@@ -134,9 +176,13 @@ impl Amx {
     /// ```
     pub fn exec(&self, index: AmxExecIdx) -> AmxResult<i32> {
         let exec = Exec::from_table(self.fn_table);
+        let index = index.into();
         let mut retval = 0;
 
-        amx_try!(exec(self.ptr, &mut retval, index.into()));
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("exec", amx = ?self.ptr, index).entered();
+
+        amx_try!(exec(self.ptr, &mut retval, index));
 
         Ok(retval)
     }
@@ -198,7 +244,7 @@ impl Amx {
     /// #   Ok(())
     /// # }
     /// ```
-    pub fn find_pubvar<T: Sized + AmxPrimitive>(&self, name: &str) -> AmxResult<Ref<T>> {
+    pub fn find_pubvar<T: Sized + AmxPrimitive>(&self, name: &str) -> AmxResult<Ref<'_, T>> {
         let find_pubvar = FindPubVar::from_table(self.fn_table);
         let c_str = CString::new(name).map_err(|_| AmxError::NotFound)?;
         let mut cell_ptr = 0;
@@ -238,15 +284,103 @@ impl Amx {
     /// # }
     /// ```
     ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the address is out of the AMX data.
+    ///
     /// [`Ref<T>`]: ../cell/struct.Ref.html
-    pub fn get_ref<T: Sized + AmxPrimitive>(&self, address: i32) -> AmxResult<Ref<T>> {
+    pub fn get_ref<T: Sized + AmxPrimitive>(&self, address: i32) -> AmxResult<Ref<'_, T>> {
+        self.check_bounds(address, 1)?;
+        unsafe { self.get_ref_unchecked(address) }
+    }
+
+    /// Get a reference ([`Ref<T>`]) to a value stored inside an AMX without checking bounds of the AMX data.
+    ///
+    /// # Safety
+    /// The address **must** be inside of the AMX data, see [`check_bounds`].
+    ///
+    /// [`Ref<T>`]: ../cell/struct.Ref.html
+    /// [`check_bounds`]: #method.check_bounds
+    pub unsafe fn get_ref_unchecked<T: Sized + AmxPrimitive>(&self, address: i32) -> AmxResult<Ref<'_, T>> {
         let get_addr = GetAddr::from_table(self.fn_table);
         let mut dest = 0;
         let mut dest_addr = std::ptr::addr_of_mut!(dest);
-        
-        amx_try!(get_addr(self.ptr,address,&mut dest_addr));
 
-        unsafe { Ok(Ref::new(address, dest_addr as *mut T)) }
+        amx_try!(get_addr(self.ptr, address, &mut dest_addr));
+
+        Ok(Ref::new(self, address, dest_addr as *mut T))
+    }
+
+    /// Check that `cells` cells starting from an AMX `address` are inside of the AMX data (data section and heap or stack).
+    ///
+    /// All constructors of [`Ref`], [`Buffer`] and [`AmxString`] do this check, except `unchecked` ones.
+    ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the range is out of the AMX data.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    /// use samp_sdk::cell::{AmxCell, UnsizedBuffer};
+    /// use samp_sdk::error::AmxResult;
+    ///
+    /// // native: IsValidArray(array[], size);
+    /// fn is_valid_array(amx: &Amx, array: UnsizedBuffer, size: usize) -> AmxResult<bool> {
+    ///     Ok(amx.check_bounds(array.as_cell(), size).is_ok())
+    /// }
+    /// ```
+    ///
+    /// [`Ref`]: ../cell/struct.Ref.html
+    /// [`Buffer`]: ../cell/buffer/struct.Buffer.html
+    /// [`AmxString`]: ../cell/string/struct.AmxString.html
+    pub fn check_bounds(&self, address: i32, cells: usize) -> AmxResult<()> {
+        let (hea, stk, stp) = self.data_registers()?;
+
+        let size = cells
+            .checked_mul(std::mem::size_of::<i32>())
+            .and_then(|size| i32::try_from(size).ok())
+            .ok_or(AmxError::MemoryAccess)?;
+
+        let end = address.checked_add(size).ok_or(AmxError::MemoryAccess)?;
+
+        let in_heap = address >= 0 && end <= hea;
+        let in_stack = address >= stk && end <= stp;
+
+        if in_heap || in_stack {
+            Ok(())
+        } else {
+            Err(AmxError::MemoryAccess)
+        }
+    }
+
+    /// Return a count of cells from an address to the end of the heap or the stack it points into.
+    pub(crate) fn cells_after(&self, address: i32) -> AmxResult<usize> {
+        let (hea, stk, stp) = self.data_registers()?;
+
+        let end = if address >= 0 && address < hea {
+            hea
+        } else if address >= stk && address < stp {
+            stp
+        } else {
+            return Err(AmxError::MemoryAccess);
+        };
+
+        Ok((end - address) as usize / std::mem::size_of::<i32>())
+    }
+
+    // `hea`, `stk` and `stp` registers, the heap is `0..hea` and the stack is `stk..stp` of the data section
+    fn data_registers(&self) -> AmxResult<(i32, i32, i32)> {
+        let amx = unsafe { self.amx().as_ref() };
+        let header = unsafe { self.header().as_ref() };
+        let (hea, stk, stp) = (amx.hea, amx.stk, amx.stp);
+
+        // the AMX registers can't point out of the data described by the header
+        let data_size = header.stp - header.dat;
+
+        if hea < 0 || hea > stk || stp > data_size {
+            return Err(AmxError::MemoryAccess);
+        }
+
+        Ok((hea, stk, stp))
     }
 
     #[inline(always)]
@@ -270,15 +404,78 @@ impl Amx {
         Ok(())
     }
 
+    /// Raise a run-time error from a native, the AMX aborts a running public when the native returns
+    /// and [`exec`] of that public returns the error.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::amx::Amx;
+    /// use samp_sdk::error::{AmxError, AmxResult};
+    ///
+    /// // native: Divide(a, b);
+    /// fn divide(amx: &Amx, a: i32, b: i32) -> AmxResult<i32> {
+    ///     if b == 0 {
+    ///         amx.raise_error(AmxError::Divide)?;
+    ///         return Ok(0);
+    ///     }
+    ///
+    ///     Ok(a / b)
+    /// }
+    /// ```
+    ///
+    /// [`exec`]: #method.exec
+    pub fn raise_error(&self, error: AmxError) -> AmxResult<()> {
+        let raise_error = RaiseError::from_table(self.fn_table);
+
+        amx_try!(raise_error(self.ptr, error as i32));
+
+        Ok(())
+    }
+
     /// Returns the length of a string in characters
     ///
-    pub fn strlen<'a>(&'a self, value: *const i32) -> AmxResult<usize> {
+    pub fn strlen(&self, value: *const i32) -> AmxResult<usize> {
         let strlen = StrLen::from_table(self.fn_table);
         let mut len = 0;
         amx_try!(strlen(value, &mut len));
         Ok(len as usize)
     }
 
+    /// Return a length in bytes of a string of Unicode code points (a char in a cell) encoded in UTF-8.
+    pub fn utf8_len(&self, value: *const i32) -> AmxResult<usize> {
+        let utf8_len = UTF8Len::from_table(self.fn_table);
+        let mut len = 0;
+        amx_try!(utf8_len(value, &mut len));
+        Ok(len as usize)
+    }
+
+    /// Check that a string is valid UTF-8 and return its length in chars.
+    pub fn utf8_check(&self, string: &CStr) -> AmxResult<usize> {
+        let utf8_check = UTF8Check::from_table(self.fn_table);
+        let mut len = 0;
+        amx_try!(utf8_check(string.as_ptr(), &mut len));
+        Ok(len as usize)
+    }
+
+    /// Decode the first char of a UTF-8 string, return a code point and a count of decoded bytes.
+    pub fn utf8_get(&self, string: &CStr) -> AmxResult<(i32, usize)> {
+        let utf8_get = UTF8Get::from_table(self.fn_table);
+        let start = string.as_ptr();
+        let mut end = start;
+        let mut value = 0;
+        amx_try!(utf8_get(start, &mut end, &mut value));
+        Ok((value, end as usize - start as usize))
+    }
+
+    /// Encode a code point into UTF-8 and return a count of written bytes.
+    pub fn utf8_put(&self, buffer: &mut [u8], value: i32) -> AmxResult<usize> {
+        let utf8_put = UTF8Put::from_table(self.fn_table);
+        let start = buffer.as_mut_ptr() as *mut i8;
+        let mut end = start;
+        amx_try!(utf8_put(start, &mut end, buffer.len() as i32, value));
+        Ok(end as usize - start as usize)
+    }
+
     /// Get a heap [`Allocator`] for current [`Amx`].
     ///
     /// # Example
@@ -304,7 +501,7 @@ impl Amx {
     ///
     /// [`Allocator`]: struct.Allocator.html
     /// [`Amx`]: struct.Amx.html
-    pub fn allocator(&self) -> Allocator {
+    pub fn allocator(&self) -> Allocator<'_> {
         Allocator::new(self)
     }
 
@@ -365,14 +562,14 @@ impl<'amx> Allocator<'amx> {
     /// #       Ok(())
     /// # }
     /// ```
-    pub fn allot<T: Sized + AmxPrimitive>(&self, init_value: T) -> AmxResult<Ref<T>> {
+    pub fn allot<T: Sized + AmxPrimitive>(&self, init_value: T) -> AmxResult<Ref<'_, T>> {
         let mut cell = self.amx.allot(1)?;
         *cell = init_value;
 
         Ok(cell)
     }
 
-    /// Allocate custom sized buffer on the heap.
+    /// Allocate custom sized buffer of `T` on the heap.
     ///
     /// # Example
     /// ```rust,no_run
@@ -388,7 +585,7 @@ impl<'amx> Allocator<'amx> {
     /// let allocator = amx.allocator();
     ///
     /// let size = 3;
-    /// let mut buffer = allocator.allot_buffer(size)?;
+    /// let mut buffer = allocator.allot_buffer::<i32>(size)?;
     /// let player_id = 10;
     ///
     /// buffer[0] = 5;
@@ -402,14 +599,17 @@ impl<'amx> Allocator<'amx> {
     /// #
     /// #       Ok(())
     /// # }
-    pub fn allot_buffer(&self, size: usize) -> AmxResult<Buffer> {
+    pub fn allot_buffer<T: AmxPrimitive>(&self, size: usize) -> AmxResult<Buffer<'_, T>> {
         let buffer = self.amx.allot(size)?;
 
-        Ok(Buffer::new(buffer, size))
+        // allot never returns memory out of the heap
+        Ok(unsafe { Buffer::new_unchecked(buffer, size) })
     }
 
     /// Allocate an array on the heap, copy values from the passed array and return `Buffer` containing reference to the allocated cell.
     ///
+    /// Elements are copied as is, so an array of `f32` is passed as a `Float:array[]`.
+    ///
     /// # Example
     /// ```rust,no_run
     /// use samp_sdk::amx::Amx;
@@ -430,22 +630,125 @@ impl<'amx> Allocator<'amx> {
     /// amx.push(buffer)?;
     /// amx.push(player_id)?;
     /// amx.exec(public_fn)?;
+    ///
+    /// // forward OnPositionChanged(player_id, Float:pos[3]);
+    /// let public_fn = amx.find_public("OnPositionChanged")?;
+    /// let position = allocator.allot_array(&[1.5_f32, 12.0, 4.25])?;
+    ///
+    /// amx.push(position)?;
+    /// amx.push(player_id)?;
+    /// amx.exec(public_fn)?;
     /// #
     /// #       Ok(())
     /// # }
-    pub fn allot_array<T>(&self, array: &[T]) -> AmxResult<Buffer>
+    pub fn allot_array<T>(&self, array: &[T]) -> AmxResult<Buffer<'_, T>>
     where
-        T: AmxCell<'amx> + AmxPrimitive,
+        T: AmxPrimitive + Copy,
     {
         let mut buffer = self.allot_buffer(array.len())?;
+        buffer.copy_from_slice(array);
+
+        Ok(buffer)
+    }
+
+    /// Allocate a two-dimensional array on the heap and copy rows from `data`, which contains `rows * cols` elements.
+    ///
+    /// # Errors
+    /// Return `AmxError::Params` when a length of `data` doesn't match the sizes.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnRouteFound(const Float:points[][3], count);
+    /// let public_fn = amx.find_public("OnRouteFound")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let points = [0.0, 0.0, 3.0, 10.5, 2.0, 3.0];
+    /// let route = allocator.allot_array2d(2, 3, &points)?;
+    ///
+    /// amx.push(route.rows())?;
+    /// amx.push(route)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    pub fn allot_array2d<T>(&self, rows: usize, cols: usize, data: &[T]) -> AmxResult<Array2D<'_, T>>
+    where
+        T: AmxPrimitive + Copy,
+    {
+        let base = self.allot_nd(&[rows, cols], data)?;
+        Array2D::new(base, rows, cols)
+    }
 
-        let slice = buffer.as_mut_slice();
+    /// Allocate a multi-dimensional array on the heap with indirection vectors Pawn expects
+    /// and copy `data`, which contains all elements in row-major order.
+    ///
+    /// # Errors
+    /// Return `AmxError::Params` when `dims` is empty or a length of `data` doesn't match the sizes.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnBoardChanged(const board[3][3][2]);
+    /// let public_fn = amx.find_public("OnBoardChanged")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let board = [0; 18];
+    /// let board = allocator.allot_array_nd(&[3, 3, 2], &board)?;
+    ///
+    /// amx.push(board)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    pub fn allot_array_nd<T>(&self, dims: &[usize], data: &[T]) -> AmxResult<ArrayND<'_, T>>
+    where
+        T: AmxPrimitive + Copy,
+    {
+        let base = self.allot_nd(dims, data)?;
+        ArrayND::new(base, dims)
+    }
 
-        for (idx, item) in array.iter().enumerate() {
-            slice[idx] = item.as_cell();
+    fn allot_nd<T>(&self, dims: &[usize], data: &[T]) -> AmxResult<Ref<'_, i32>>
+    where
+        T: AmxPrimitive + Copy,
+    {
+        if dims.is_empty() || dims.iter().product::<usize>() != data.len() {
+            return Err(AmxError::Params);
         }
 
-        Ok(buffer)
+        let data_start = indirection_len(dims);
+        let mut base = self.amx.allot::<i32>(data_start + data.len())?;
+        let address = base.address();
+        let ptr = base.as_mut_ptr();
+
+        // allot never returns memory out of the heap
+        unsafe {
+            let mut cells = Buffer::new_unchecked(base, data_start);
+            fill_indirection(&mut cells, dims);
+
+            let data_ref = Ref::new(
+                self.amx,
+                address + (data_start * std::mem::size_of::<i32>()) as i32,
+                ptr.add(data_start) as *mut T,
+            );
+
+            Buffer::new_unchecked(data_ref, data.len()).copy_from_slice(data);
+
+            Ok(Ref::new(self.amx, address, ptr))
+        }
     }
 
     /// Alocate a string, copy passed `&str` and return `AmxString` pointing to an `Amx` cell.
@@ -472,19 +775,128 @@ impl<'amx> Allocator<'amx> {
     /// #
     /// #       Ok(())
     /// # }
-    pub fn allot_string(&self, string: &str) -> AmxResult<AmxString> {
-        let bytes = Allocator::string_bytes(string);
+    pub fn allot_string(&self, string: &str) -> AmxResult<AmxString<'_>> {
+        let bytes = string::encode(self.amx, string);
         let buffer = self.allot_buffer(bytes.len() + 1)?;
 
         Ok(unsafe { AmxString::new(buffer, bytes.as_ref()) })
     }
 
-    fn string_bytes<'a>(string: &'a str) -> Cow<'a, [u8]> {
-        #[cfg(feature = "encoding")]
-        return encoding::get().encode(string).0;
+    /// Alocate a packed string (4 chars in a cell), copy passed `&str` and return `AmxString` pointing to an `Amx` cell.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnMessage(const message[]);
+    /// let public_fn = amx.find_public("OnMessage")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let message = allocator.allot_packed_string("a long message takes a quarter of cells")?;
+    ///
+    /// amx.push(message)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    pub fn allot_packed_string(&self, string: &str) -> AmxResult<AmxString<'_>> {
+        let bytes = string::encode(self.amx, string);
+        let buffer = self.allot_buffer(bytes.len() / 4 + 1)?;
+
+        Ok(unsafe { AmxString::new_packed(buffer, bytes.as_ref()) })
+    }
+
+    /// Alocate a string of Unicode code points (a char in a cell) for scripts that use UTF-8 strings.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnChatMessage(const message[]);
+    /// let public_fn = amx.find_public("OnChatMessage")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let message = allocator.allot_unicode_string("gg 🎉")?;
+    ///
+    /// amx.push(message)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    pub fn allot_unicode_string(&self, string: &str) -> AmxResult<AmxString<'_>> {
+        let buffer = self.allot_buffer(string.chars().count() + 1)?;
+
+        Ok(unsafe { AmxString::new_unicode(buffer, string) })
+    }
+
+    /// Allocate a packed array of `size` chars on the heap (`new array[size char]` in Pawn).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnMapLoaded(const tiles[], count);
+    /// let public_fn = amx.find_public("OnMapLoaded")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let mut tiles = allocator.allot_packed_buffer(10)?;
+    /// tiles[3] = 200;
+    ///
+    /// amx.push(10)?;
+    /// amx.push(tiles)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    pub fn allot_packed_buffer(&self, size: usize) -> AmxResult<PackedBuffer<'_>> {
+        let buffer = self.allot_buffer(size.div_ceil(4))?;
+        Ok(PackedBuffer::new(buffer))
+    }
+
+    /// Alocate a string encoded with given encoding instead of an encoding of the `Amx`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use samp_sdk::amx::Amx;
+    /// use samp_sdk::encoding::WINDOWS_1251;
+    ///
+    /// # use samp_sdk::error::AmxResult;
+    /// #
+    /// # fn main() -> AmxResult<()> {
+    /// # let amx = Amx::new(std::ptr::null_mut(), 0);
+    /// // forward OnCyrillicMessage(const message[]);
+    /// let public_fn = amx.find_public("OnCyrillicMessage")?;
+    /// let allocator = amx.allocator();
+    ///
+    /// let message = allocator.allot_string_with("Привет", WINDOWS_1251)?;
+    ///
+    /// amx.push(message)?;
+    /// amx.exec(public_fn)?;
+    /// #
+    /// #       Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "encoding")]
+    pub fn allot_string_with(&self, string: &str, encoding: &'static Encoding) -> AmxResult<AmxString<'_>> {
+        let bytes = encoding.encode(string).0;
+        let buffer = self.allot_buffer(bytes.len() + 1)?;
 
-        #[cfg(not(feature = "encoding"))]
-        return Cow::from(string.as_bytes());
+        Ok(unsafe { AmxString::new(buffer, bytes.as_ref()) })
     }
 }
 
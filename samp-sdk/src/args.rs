@@ -52,6 +52,7 @@ impl<'a> Args<'a> {
     /// Return the next argument in the list (like an iterator).
     ///
     /// When there is no arguments left returns `None`.
+    #[allow(clippy::should_implement_trait)]
     pub fn next<T: AmxCell<'a> + 'a>(&mut self) -> Option<T> {
         let result = self.get(self.offset);
         self.offset += 1;
@@ -83,7 +84,7 @@ impl<'a> Args<'a> {
     /// }
     /// ```
     pub fn get<T: AmxCell<'a> + 'a>(&self, offset: usize) -> Option<T> {
-        if offset > self.count() {
+        if offset >= self.count() {
             return None;
         }
 
@@ -1,17 +1,18 @@
 //! Different smart-pointers to work around raw AMX values.
-use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
 use crate::amx::Amx;
 use crate::error::AmxResult;
 
+pub mod array;
 pub mod buffer;
 pub mod repr;
 pub mod string;
 
-pub use buffer::{Buffer, UnsizedBuffer};
+pub use array::{Array2D, ArrayND};
+pub use buffer::{Buffer, PackedBuffer, UnsizedBuffer};
 pub use repr::{AmxCell, AmxPrimitive};
-pub use string::AmxString;
+pub use string::{AmxString, AmxStringMut, AmxStringOut, Chars, DecodeError};
 
 /// A reference to a cell in the [`Amx`].
 ///
@@ -23,9 +24,9 @@ pub use string::AmxString;
 /// [`Box<T>`]: https://doc.rust-lang.org/std/boxed/struct.Box.html
 /// [`Rc<T>`]: https://doc.rust-lang.org/std/rc/struct.Rc.html
 pub struct Ref<'amx, T: Sized + AmxPrimitive> {
+    amx: &'amx Amx,
     amx_addr: i32,
     phys_addr: *mut T,
-    marker: PhantomData<&'amx Amx>,
 }
 
 impl<'amx, T: Sized + AmxPrimitive> Ref<'amx, T> {
@@ -33,16 +34,17 @@ impl<'amx, T: Sized + AmxPrimitive> Ref<'amx, T> {
     ///
     /// # Safety
     /// `Ref<T>` **should** be alive as long as `phys_addr` or it will dangling pointer.
+    /// `amx_addr` isn't checked, it **must** be inside of the AMX data.
     ///
     /// It's not recomended to use directly, instead get a reference from [`Args`] or [`Amx::get_ref`].
     ///
     /// [`Args`]: ../args/struct.Args.html
     /// [`Amx::get_ref`]: ../amx/struct.Amx.html#method.get_ref
-    pub unsafe fn new(amx_addr: i32, phys_addr: *mut T) -> Ref<'amx, T> {
+    pub unsafe fn new(amx: &'amx Amx, amx_addr: i32, phys_addr: *mut T) -> Ref<'amx, T> {
         Ref {
+            amx,
             amx_addr,
             phys_addr,
-            marker: PhantomData,
         }
     }
 
@@ -62,6 +64,14 @@ impl<'amx, T: Sized + AmxPrimitive> Ref<'amx, T> {
         self.amx_addr
     }
 
+    /// Get an [`Amx`] that owns the cell.
+    ///
+    /// [`Amx`]: ../amx/struct.Amx.html
+    #[inline]
+    pub fn amx(&self) -> &'amx Amx {
+        self.amx
+    }
+
     /// Get a pointer to a memory cell.
     #[inline]
     pub fn as_ptr(&self) -> *const T {
@@ -0,0 +1,246 @@
+//! Contains types to interact with multi-dimensional AMX arrays.
+//!
+//! Pawn keeps a multi-dimensional array as an indirection vector for every dimension except the last one,
+//! followed by the data. Each cell of an indirection vector holds an offset in bytes from the cell itself
+//! to a next dimension or to a row of the data.
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use super::{AmxCell, AmxPrimitive, AmxString, Buffer, Ref};
+use crate::amx::Amx;
+use crate::error::{AmxError, AmxResult};
+
+const CELL_SIZE: i32 = std::mem::size_of::<i32>() as i32;
+
+/// A view of a two-dimensional AMX array, like `new names[MAX_PLAYERS][24]`.
+///
+/// Pawn doesn't pass sizes of an array, so they should be known by a native or passed as arguments.
+///
+/// # Example
+/// ```
+/// use samp_sdk::cell::{Array2D, Ref, UnsizedBuffer};
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: SumRows(const values[][], rows, cols, sums[]);
+/// fn sum_rows(amx: &Amx, values: Ref<i32>, rows: usize, cols: usize, sums: UnsizedBuffer) -> AmxResult<()> {
+///     let values = Array2D::<i32>::new(values, rows, cols)?;
+///     let mut sums = sums.into_sized_buffer(rows)?;
+///
+///     for (idx, sum) in sums.iter_mut().enumerate() {
+///         *sum = values.row(idx)?.iter().sum();
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Array2D<'amx, T: AmxPrimitive = i32> {
+    inner: ArrayND<'amx, T>,
+}
+
+impl<'amx, T: AmxPrimitive> Array2D<'amx, T> {
+    /// Create a view of an array from a reference to its indirection vector.
+    ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the indirection vector is out of the AMX data.
+    pub fn new(base: Ref<'amx, i32>, rows: usize, cols: usize) -> AmxResult<Array2D<'amx, T>> {
+        Ok(Array2D {
+            inner: ArrayND::new(base, &[rows, cols])?,
+        })
+    }
+
+    /// Return an amount of rows.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.inner.dims[0]
+    }
+
+    /// Return a length of a row.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.inner.dims[1]
+    }
+
+    /// Return a row with given index.
+    ///
+    /// # Errors
+    /// Return `AmxError::Bounds` when the index is out of the array
+    /// and `AmxError::MemoryAccess` when the row is out of the AMX data.
+    pub fn row(&self, idx: usize) -> AmxResult<Buffer<'amx, T>> {
+        self.inner.row(&[idx])
+    }
+
+    /// Return a row with given index as a string, like `names[playerid]`.
+    ///
+    /// # Errors
+    /// Return `AmxError::Bounds` when the index is out of the array
+    /// and `AmxError::MemoryAccess` when the string is out of the AMX data.
+    pub fn row_string(&self, idx: usize) -> AmxResult<AmxString<'amx>> {
+        self.inner.row_string(&[idx])
+    }
+}
+
+impl<'amx, T: AmxPrimitive> AmxCell<'amx> for Array2D<'amx, T> {
+    #[inline]
+    fn as_cell(&self) -> i32 {
+        self.inner.as_cell()
+    }
+}
+
+/// A view of an AMX array with any amount of dimensions, like `new map[10][10][4]`.
+///
+/// # Example
+/// ```
+/// use samp_sdk::cell::{ArrayND, Ref};
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: GetCellHeight(const Float:heights[][][], x, y, z, &Float:height);
+/// fn get_cell_height(amx: &Amx, heights: Ref<i32>, x: usize, y: usize, z: usize, mut height: Ref<f32>) -> AmxResult<()> {
+///     let heights = ArrayND::<f32>::new(heights, &[16, 16, 8])?;
+///
+///     *height = match heights.row(&[x, y])?.get(z) {
+///         Some(&value) => value,
+///         None => 0.0,
+///     };
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ArrayND<'amx, T: AmxPrimitive = i32> {
+    amx: &'amx Amx,
+    address: i32,
+    dims: Vec<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<'amx, T: AmxPrimitive> ArrayND<'amx, T> {
+    /// Create a view of an array from a reference to its first indirection vector and sizes of all dimensions.
+    ///
+    /// # Errors
+    /// Return `AmxError::Params` when `dims` is empty
+    /// and `AmxError::MemoryAccess` when the first indirection vector is out of the AMX data.
+    pub fn new(base: Ref<'amx, i32>, dims: &[usize]) -> AmxResult<ArrayND<'amx, T>> {
+        if dims.is_empty() {
+            return Err(AmxError::Params);
+        }
+
+        let amx = base.amx();
+        let address = base.address();
+
+        if dims.len() > 1 {
+            amx.check_bounds(address, dims[0])?;
+        }
+
+        Ok(ArrayND {
+            amx,
+            address,
+            dims: dims.to_vec(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Return sizes of all dimensions.
+    #[inline]
+    pub fn dims(&self) -> &[usize] {
+        &self.dims
+    }
+
+    /// Return a row of the last dimension, `indices` contain an index for every other dimension.
+    ///
+    /// # Errors
+    /// Return `AmxError::Params` when there is a wrong amount of indices,
+    /// `AmxError::Bounds` when an index is out of the array
+    /// and `AmxError::MemoryAccess` when the row is out of the AMX data.
+    pub fn row(&self, indices: &[usize]) -> AmxResult<Buffer<'amx, T>> {
+        let address = self.row_address(indices)?;
+        let reference = self.amx.get_ref(address)?;
+
+        Buffer::new(reference, self.dims[self.dims.len() - 1])
+    }
+
+    /// Return a row of the last dimension as a string.
+    ///
+    /// # Errors
+    /// The same as [`row`](#method.row).
+    pub fn row_string(&self, indices: &[usize]) -> AmxResult<AmxString<'amx>> {
+        let address = self.row_address(indices)?;
+
+        AmxString::from_raw(self.amx, address)
+    }
+
+    // walk through the indirection vectors
+    fn row_address(&self, indices: &[usize]) -> AmxResult<i32> {
+        if indices.len() + 1 != self.dims.len() {
+            return Err(AmxError::Params);
+        }
+
+        let mut address = self.address;
+
+        for (&idx, &dim) in indices.iter().zip(&self.dims) {
+            if idx >= dim {
+                return Err(AmxError::Bounds);
+            }
+
+            let cell_address = i32::try_from(idx)
+                .ok()
+                .and_then(|idx| idx.checked_mul(CELL_SIZE))
+                .and_then(|offset| address.checked_add(offset))
+                .ok_or(AmxError::MemoryAccess)?;
+            let offset = self.amx.get_ref::<i32>(cell_address)?;
+
+            address = cell_address
+                .checked_add(*offset)
+                .ok_or(AmxError::MemoryAccess)?;
+        }
+
+        Ok(address)
+    }
+}
+
+impl<'amx, T: AmxPrimitive> AmxCell<'amx> for ArrayND<'amx, T> {
+    #[inline]
+    fn as_cell(&self) -> i32 {
+        self.address
+    }
+}
+
+/// Fill indirection vectors in the beginning of `cells` for an array with given sizes.
+pub(crate) fn fill_indirection(cells: &mut [i32], dims: &[usize]) {
+    let data_start = indirection_len(dims);
+    let mut level_start = 0;
+    let mut level_len = 1;
+
+    for (level, &dim) in dims[..dims.len() - 1].iter().enumerate() {
+        level_len *= dim;
+
+        let next_start = level_start + level_len;
+        let next_dim = dims[level + 1];
+
+        for idx in 0..level_len {
+            let target = if level + 2 < dims.len() {
+                next_start + idx * next_dim
+            } else {
+                data_start + idx * next_dim
+            };
+
+            let cell = level_start + idx;
+            cells[cell] = (target - cell) as i32 * CELL_SIZE;
+        }
+
+        level_start = next_start;
+    }
+}
+
+/// Return a length in cells of indirection vectors of an array with given sizes.
+pub(crate) fn indirection_len(dims: &[usize]) -> usize {
+    let mut len = 0;
+    let mut level_len = 1;
+
+    for &dim in &dims[..dims.len().saturating_sub(1)] {
+        level_len *= dim;
+        len += level_len;
+    }
+
+    len
+}
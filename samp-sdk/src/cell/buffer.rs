@@ -1,82 +1,133 @@
 //! Contains types to interact with AMX arrays.
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Index, IndexMut};
 
-use super::{AmxCell, Ref};
+use super::{AmxCell, AmxPrimitive, Ref};
 use crate::amx::Amx;
 use crate::error::AmxResult;
 
 /// Contains a pointer to sequence of `Amx` cells.
 ///
-/// Can be dereferenced to a [`slice`].
+/// Can be dereferenced to a [`slice`] of `T`, which is `i32` by default.
+/// `T` must have the size of a cell (`i32`, `u32`, `f32`), otherwise the buffer doesn't compile.
 ///
 /// # Example
 /// ```
 /// use samp_sdk::cell::{UnsizedBuffer, Buffer};
 /// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
 ///
 /// // native: IGiveYouABuffer(buffer[]);
-/// fn it_gave_me_a_buffer(amx: &Amx, buffer: UnsizedBuffer, size: usize) {
-///     let mut buffer: Buffer = buffer.into_sized_buffer(size);
+/// fn it_gave_me_a_buffer(amx: &Amx, buffer: UnsizedBuffer, size: usize) -> AmxResult<()> {
+///     let mut buffer: Buffer = buffer.into_sized_buffer(size)?;
 ///     
 ///     println!("Got {:?}", buffer);
 ///     
 ///     buffer.iter_mut().map(|elem| *elem = *elem * 2);
 ///
 ///     println!("Change to {:?}", buffer);
+///     Ok(())
+/// }
+/// ```
+///
+///
+/// A `Float:array[]` can be used without converting every element:
+/// ```
+/// use samp_sdk::cell::UnsizedBuffer;
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: ScaleVector(Float:vector[], size, Float:scale);
+/// fn scale_vector(amx: &Amx, vector: UnsizedBuffer<f32>, size: usize, scale: f32) -> AmxResult<()> {
+///     let mut vector = vector.into_sized_buffer(size)?;
+///
+///     for coord in vector.iter_mut() {
+///         *coord *= scale;
+///     }
+///
+///     Ok(())
 /// }
 /// ```
 ///
 /// [`slice`]: https://doc.rust-lang.org/std/primitive.slice.html
-pub struct Buffer<'amx> {
-    inner: Ref<'amx, i32>,
+pub struct Buffer<'amx, T: AmxPrimitive = i32> {
+    inner: Ref<'amx, T>,
     len: usize,
 }
 
-impl<'amx> Buffer<'amx> {
+impl<'amx, T: AmxPrimitive> Buffer<'amx, T> {
+    // evaluated when a buffer of `T` is created, so `Buffer<u8>` is a compile error
+    const CELL_SIZED: () = assert!(
+        std::mem::size_of::<T>() == std::mem::size_of::<i32>(),
+        "an element of a buffer must have the size of an AMX cell"
+    );
+
     /// Create a buffer from a reference to its first element.
-    pub fn new(reference: Ref<'amx, i32>, len: usize) -> Buffer<'amx> {
+    ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the buffer is out of the AMX data.
+    pub fn new(reference: Ref<'amx, T>, len: usize) -> AmxResult<Buffer<'amx, T>> {
+        reference.amx().check_bounds(reference.address(), len)?;
+        Ok(unsafe { Buffer::new_unchecked(reference, len) })
+    }
+
+    /// Create a buffer from a reference to its first element without checking bounds of the AMX data.
+    ///
+    /// # Safety
+    /// `len` cells starting from the reference **must** be inside of the AMX data, see [`Amx::check_bounds`].
+    ///
+    /// [`Amx::check_bounds`]: ../../amx/struct.Amx.html#method.check_bounds
+    pub unsafe fn new_unchecked(reference: Ref<'amx, T>, len: usize) -> Buffer<'amx, T> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::CELL_SIZED;
+
         Buffer {
             inner: reference,
             len,
         }
     }
 
+    /// Return the `Amx` the buffer belongs to.
+    #[inline]
+    pub fn amx(&self) -> &'amx Amx {
+        self.inner.amx()
+    }
+
     /// Extracts a slice containing the entire buffer.
     #[inline]
-    pub fn as_slice(&self) -> &[i32] {
+    pub fn as_slice(&self) -> &[T] {
         unsafe { std::slice::from_raw_parts(self.inner.as_ptr(), self.len) }
     }
 
     /// Extracts a mutable slice of the entire buffer.
     #[inline]
-    pub fn as_mut_slice(&mut self) -> &mut [i32] {
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
         unsafe { std::slice::from_raw_parts_mut(self.inner.as_mut_ptr(), self.len) }
     }
 }
 
 // Buffer cannot be parsed
-impl<'amx> AmxCell<'amx> for Buffer<'amx> {
+impl<'amx, T: AmxPrimitive> AmxCell<'amx> for Buffer<'amx, T> {
     #[inline]
     fn as_cell(&self) -> i32 {
         self.inner.as_cell()
     }
 }
 
-impl Deref for Buffer<'_> {
-    type Target = [i32];
+impl<T: AmxPrimitive> Deref for Buffer<'_, T> {
+    type Target = [T];
 
-    fn deref(&self) -> &[i32] {
+    fn deref(&self) -> &[T] {
         self.as_slice()
     }
 }
 
-impl DerefMut for Buffer<'_> {
-    fn deref_mut(&mut self) -> &mut [i32] {
+impl<T: AmxPrimitive> DerefMut for Buffer<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
         self.as_mut_slice()
     }
 }
 
-impl std::fmt::Debug for Buffer<'_> {
+impl<T: AmxPrimitive + std::fmt::Debug> std::fmt::Debug for Buffer<'_, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{:?}", self.as_slice())
     }
@@ -91,7 +142,7 @@ impl std::fmt::Debug for Buffer<'_> {
 /// # use samp_sdk::error::AmxResult;
 ///
 /// fn null_my_array(amx: &Amx, array: UnsizedBuffer, length: usize) -> AmxResult<u32> {
-///     let mut array = array.into_sized_buffer(length);
+///     let mut array = array.into_sized_buffer(length)?;
 ///
 ///     unsafe {
 ///         let slice = array.as_mut_slice();
@@ -101,46 +152,62 @@ impl std::fmt::Debug for Buffer<'_> {
 ///     return Ok(1)
 /// }
 /// ```
-pub struct UnsizedBuffer<'amx> {
-    inner: Ref<'amx, i32>,
+pub struct UnsizedBuffer<'amx, T: AmxPrimitive = i32> {
+    inner: Ref<'amx, T>,
 }
 
-impl<'amx> UnsizedBuffer<'amx> {
+impl<'amx, T: AmxPrimitive> UnsizedBuffer<'amx, T> {
     /// Convert `UnsizedBuffer` into `Buffer` with given length.
     ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the buffer with given length is out of the AMX data.
+    ///
     /// # Example
     /// ```
     /// use samp_sdk::cell::UnsizedBuffer;
     /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
     ///
-    /// fn push_ones(amx: &Amx, array: UnsizedBuffer, length: usize) {
-    ///     let mut buffer = array.into_sized_buffer(length);
+    /// fn push_ones(amx: &Amx, array: UnsizedBuffer, length: usize) -> AmxResult<()> {
+    ///     let mut buffer = array.into_sized_buffer(length)?;
     ///     let slice = buffer.as_mut_slice();
     ///     
     ///     for item in slice.iter_mut() {
     ///         *item = 1;
     ///     }
+    ///
+    ///     Ok(())
     /// }
     /// ```
-    pub fn into_sized_buffer(self, len: usize) -> Buffer<'amx> {
+    pub fn into_sized_buffer(self, len: usize) -> AmxResult<Buffer<'amx, T>> {
         Buffer::new(self.inner, len)
     }
 
+    /// Convert `UnsizedBuffer` into `Buffer` with given length without checking bounds of the AMX data.
+    ///
+    /// # Safety
+    /// `len` cells of the buffer **must** be inside of the AMX data, see [`Amx::check_bounds`].
+    ///
+    /// [`Amx::check_bounds`]: ../../amx/struct.Amx.html#method.check_bounds
+    pub unsafe fn into_sized_buffer_unchecked(self, len: usize) -> Buffer<'amx, T> {
+        Buffer::new_unchecked(self.inner, len)
+    }
+
     /// Return a raw pointer to an inner value.
     #[inline]
-    pub fn as_ptr(&self) -> *const i32 {
+    pub fn as_ptr(&self) -> *const T {
         self.inner.as_ptr()
     }
 
     /// Return a mutable raw pointer to an inner value.
     #[inline]
-    pub fn as_mut_ptr(&mut self) -> *mut i32 {
+    pub fn as_mut_ptr(&mut self) -> *mut T {
         self.inner.as_mut_ptr()
     }
 }
 
-impl<'amx> AmxCell<'amx> for UnsizedBuffer<'amx> {
-    fn from_raw(amx: &'amx Amx, cell: i32) -> AmxResult<UnsizedBuffer<'amx>> {
+impl<'amx, T: AmxPrimitive> AmxCell<'amx> for UnsizedBuffer<'amx, T> {
+    fn from_raw(amx: &'amx Amx, cell: i32) -> AmxResult<UnsizedBuffer<'amx, T>> {
         Ok(UnsizedBuffer {
             inner: amx.get_ref(cell)?,
         })
@@ -151,3 +218,117 @@ impl<'amx> AmxCell<'amx> for UnsizedBuffer<'amx> {
         self.inner.as_cell()
     }
 }
+
+/// A packed array of chars (`new array[N char]` in Pawn), which keeps 4 bytes in a cell.
+///
+/// It's indexed by bytes like `array{idx}` in Pawn, the first byte of a cell is the highest one.
+///
+/// # Example
+/// ```
+/// use samp_sdk::cell::{PackedBuffer, UnsizedBuffer};
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: CountWalls(const tiles[], count);
+/// fn count_walls(amx: &Amx, tiles: UnsizedBuffer, count: usize) -> AmxResult<usize> {
+///     // `count` chars take `count / 4` cells rounded up
+///     let tiles = PackedBuffer::new(tiles.into_sized_buffer(count.div_ceil(4))?);
+///
+///     Ok(tiles.iter().take(count).filter(|&tile| tile == b'#').count())
+/// }
+/// ```
+pub struct PackedBuffer<'amx> {
+    inner: Buffer<'amx>,
+}
+
+impl<'amx> PackedBuffer<'amx> {
+    /// Create a packed array over a buffer of cells.
+    pub fn new(buffer: Buffer<'amx>) -> PackedBuffer<'amx> {
+        PackedBuffer { inner: buffer }
+    }
+
+    /// Return a count of bytes in the array.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len() * 4
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Return a byte with given index or `None` if it's out of the array.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<u8> {
+        if idx < self.len() {
+            Some(self[idx])
+        } else {
+            None
+        }
+    }
+
+    /// Return an iterator over all bytes of the array.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len()).map(move |idx| self[idx])
+    }
+
+    /// Return cells of the array.
+    #[inline]
+    pub fn as_buffer(&self) -> &Buffer<'amx> {
+        &self.inner
+    }
+
+    /// Return mutable cells of the array.
+    #[inline]
+    pub fn as_mut_buffer(&mut self) -> &mut Buffer<'amx> {
+        &mut self.inner
+    }
+
+    /// Convert the array back into a buffer of cells.
+    #[inline]
+    pub fn into_inner(self) -> Buffer<'amx> {
+        self.inner
+    }
+
+    // an offset of a byte in memory, since the first byte is the highest one of a cell
+    #[inline]
+    fn byte_offset(idx: usize) -> usize {
+        if cfg!(target_endian = "little") {
+            idx - idx % 4 + (3 - idx % 4)
+        } else {
+            idx
+        }
+    }
+}
+
+impl Index<usize> for PackedBuffer<'_> {
+    type Output = u8;
+
+    fn index(&self, idx: usize) -> &u8 {
+        assert!(idx < self.len(), "index {} is out of a packed array of {} bytes", idx, self.len());
+
+        unsafe { &*(self.inner.as_ptr() as *const u8).add(Self::byte_offset(idx)) }
+    }
+}
+
+impl IndexMut<usize> for PackedBuffer<'_> {
+    fn index_mut(&mut self, idx: usize) -> &mut u8 {
+        assert!(idx < self.len(), "index {} is out of a packed array of {} bytes", idx, self.len());
+
+        unsafe { &mut *(self.inner.as_mut_ptr() as *mut u8).add(Self::byte_offset(idx)) }
+    }
+}
+
+impl<'amx> AmxCell<'amx> for PackedBuffer<'amx> {
+    #[inline]
+    fn as_cell(&self) -> i32 {
+        self.inner.as_cell()
+    }
+}
+
+impl std::fmt::Debug for PackedBuffer<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
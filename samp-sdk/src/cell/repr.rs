@@ -21,9 +21,16 @@ where
 
 /// A marker showing that a value can be stored directly on a stack or a heap of an AMX.
 ///
-/// Types: i8, u8, i16, u16, i32, u32, usize, isize, f32, bool
+/// Types: i8, u8, i16, u16, i32, u32, usize, isize, f32
 ///
 /// There is no values that's bigger than 4 bytes, because size of an AMX cell is 32 bits.
+///
+/// `bool` isn't a primitive: a script can keep any value in a cell and most of them aren't a valid `bool`.
+/// Booleans are read by value (a `bool` argument of a native is `cell != 0`), a `Ref` or a `Buffer` of them
+/// is taken as `i32` and compared with zero.
+///
+/// # Safety
+/// Implementors **must** have the size and alignment of a 32-bit cell at most.
 pub unsafe trait AmxPrimitive
 where
     Self: Sized,
@@ -75,7 +82,7 @@ impl AmxCell<'_> for f32 {
     fn as_cell(&self) -> i32 {
         // can't use `as` here because a float value will be an integer
         // for example if you pass 10.0 (0x41200000) it will be 10 (0x0A)
-        unsafe { std::mem::transmute(*self) }
+        self.to_bits() as i32
     }
 }
 
@@ -91,4 +98,3 @@ impl AmxCell<'_> for bool {
 }
 
 unsafe impl AmxPrimitive for f32 {}
-unsafe impl AmxPrimitive for bool {}
@@ -1,23 +1,32 @@
 //! String interperation inside an AMX.
-use std::fmt;
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
 
-use super::{AmxCell, Buffer, UnsizedBuffer};
+use super::{AmxCell, Buffer, Ref, UnsizedBuffer};
 use crate::amx::Amx;
-use crate::error::AmxResult;
+use crate::error::{AmxError, AmxResult};
 #[cfg(feature = "encoding")]
-use crate::encoding;
+use crate::encoding::Encoding;
 
 const MAX_UNPACKED: i32 = 0x00FF_FFFF;
+const MAX_BYTE: i32 = 0xFF;
 
 // A wrapper around an AMX string.
 pub struct AmxString<'amx> {
     inner: Buffer<'amx>,
     // real length of the string
     len: usize,
+    // keeps a Unicode code point in a cell, see `is_unicode`
+    unicode: bool,
 }
 
 impl<'amx> AmxString<'amx> {
     /// Create a new AmxString from an allocated buffer and fill it with a string
+    ///
+    /// # Safety
+    /// The buffer **must** be at least one cell longer than `bytes` to store the terminating zero.
     pub unsafe fn new(mut buffer: Buffer<'amx>, bytes: &[u8]) -> AmxString<'amx> {
         // let _ = put_in_buffer(&mut buffer, string); // here can't be an error.
         for (idx, byte) in bytes.iter().enumerate() {
@@ -27,40 +36,103 @@ impl<'amx> AmxString<'amx> {
         buffer[bytes.len()] = 0;
 
         AmxString {
-            len: buffer.len(),
+            len: bytes.len(),
+            inner: buffer,
+            unicode: false,
+        }
+    }
+
+    /// Create a new AmxString from an allocated buffer and fill it with a packed string (4 chars in a cell).
+    ///
+    /// # Safety
+    /// The buffer **must** have at least `bytes.len() / 4 + 1` cells to store the terminating zero.
+    pub unsafe fn new_packed(mut buffer: Buffer<'amx>, bytes: &[u8]) -> AmxString<'amx> {
+        write_packed(&mut buffer, bytes);
+
+        AmxString {
+            len: bytes.len(),
+            inner: buffer,
+            unicode: false,
+        }
+    }
+
+    /// Create a new AmxString from an allocated buffer and fill it with Unicode code points of a string (a char in a cell).
+    ///
+    /// # Safety
+    /// The buffer **must** be at least one cell longer than a count of chars of `string` to store the terminating zero.
+    pub unsafe fn new_unicode(mut buffer: Buffer<'amx>, string: &str) -> AmxString<'amx> {
+        let len = write_unicode(&mut buffer, string);
+        let unicode = is_unicode(buffer.amx(), &buffer, len);
+
+        AmxString {
+            len,
             inner: buffer,
+            unicode,
         }
     }
 
     /// Convert an AMX string to a `Vec<u8>`.
+    ///
+    /// A cell of a Unicode string (see [`is_unicode`](#method.is_unicode)) is cut to its lowest byte.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut vec = Vec::with_capacity(self.len);
-        // packed string
-        if self.inner[0] > MAX_UNPACKED {
-            let mut ptr = self.inner.as_ptr();
-            let mut mark = 3;
-            for _ in 0..self.len {
-                let ch = (unsafe { *ptr } >> mark * 8) as u8;
-                if ch == b'\0' {
-                    break;
-                }
-                vec.push(ch);
-                mark = (mark + 3) % 4;
-                if mark == 3 {
-                    ptr = unsafe { ptr.add(1) };
-                }
-            }
-        } else {
-            for item in self.inner.iter().take(self.len) {
-                vec.push(*item as u8);
-            }
-        }
+        self.bytes().collect()
+    }
+
+    /// Return an iterator over bytes of the string, without copying it from the AMX.
+    ///
+    /// A cell of a Unicode string is cut to its lowest byte, the same as in [`to_bytes`](#method.to_bytes).
+    pub fn bytes(&self) -> impl ExactSizeIterator<Item = u8> + '_ {
+        let packed = is_packed(self.inner[0]);
+        (0..self.len).map(move |idx| byte_at(&self.inner, packed, idx))
+    }
+
+    /// Return an iterator over chars of the string, decoded the same way as [`to_string`](#method.to_string),
+    /// without copying it from the AMX. Without the `encoding` feature, each byte of invalid UTF-8 becomes a separate `U+FFFD`.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: CountDigits(const text[]);
+    /// fn count_digits(amx: &Amx, text: AmxString) -> AmxResult<usize> {
+    ///     Ok(text.chars().filter(|ch| ch.is_ascii_digit()).count())
+    /// }
+    /// ```
+    pub fn chars(&self) -> Chars<'_, 'amx> {
+        Chars::new(self)
+    }
+
+    /// Checks that the string is equal to `other`, ignoring case of ASCII letters.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: IsHelpCommand(const cmdtext[]);
+    /// fn is_help_command(amx: &Amx, cmdtext: AmxString) -> AmxResult<bool> {
+    ///     Ok(cmdtext.eq_ignore_ascii_case("/help") || cmdtext.starts_with("/help "))
+    /// }
+    /// ```
+    pub fn eq_ignore_ascii_case(&self, other: &str) -> bool {
+        self.chars()
+            .map(|ch| ch.to_ascii_lowercase())
+            .eq(other.chars().map(|ch| ch.to_ascii_lowercase()))
+    }
 
-        vec
+    /// Returns `true` when the string starts with `prefix`.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        let mut chars = self.chars();
+        prefix.chars().all(|ch| chars.next() == Some(ch))
     }
 
     /// Convert an AMX string to a `String`.
-    /// Only ASCII chars by default. Pass `cp1251` to crate features to enable Windows 1251 encoding (TODO).
+    /// Bytes are decoded as UTF-8 by default or with an encoding of the `Amx` when the `encoding` feature is enabled.
+    ///
+    /// Invalid bytes are replaced with `U+FFFD`, the same as [`to_string_lossy`](#method.to_string_lossy).
     ///
     /// # Example
     /// ```
@@ -90,12 +162,216 @@ impl<'amx> AmxString<'amx> {
     /// }
     /// # }
     /// ```
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
+        self.to_string_lossy()
+    }
+
+    /// Convert an AMX string to a `str` without allocating when it's plain ASCII that fits in `buf`,
+    /// otherwise it's converted by [`to_string`](#method.to_string).
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: IsAdminCommand(const cmdtext[]);
+    /// fn is_admin_command(amx: &Amx, cmdtext: AmxString) -> AmxResult<bool> {
+    ///     let mut buf = [0; 128];
+    ///     let cmdtext = cmdtext.to_str_cow(&mut buf);
+    ///
+    ///     Ok(cmdtext.starts_with("/a ") || cmdtext.starts_with("/admin "))
+    /// }
+    /// ```
+    pub fn to_str_cow<'b>(&self, buf: &'b mut [u8]) -> Cow<'b, str> {
         #[cfg(feature = "encoding")]
-        return encoding::get().decode(&self.to_bytes()).0.into_owned();
+        let ascii_compatible = self.inner.amx().encoding().is_ascii_compatible();
+        #[cfg(not(feature = "encoding"))]
+        let ascii_compatible = true;
+
+        if !ascii_compatible || self.len > buf.len() || self.is_unicode() {
+            return Cow::Owned(self.to_string());
+        }
+
+        let buf = &mut buf[..self.len];
+
+        for (dest, byte) in buf.iter_mut().zip(self.bytes()) {
+            if !byte.is_ascii() {
+                return Cow::Owned(self.to_string());
+            }
+
+            *dest = byte;
+        }
+
+        // checked above that all bytes are ASCII
+        Cow::Borrowed(unsafe { std::str::from_utf8_unchecked(buf) })
+    }
+
+    /// Convert an AMX string to a `String`, replacing invalid bytes with `U+FFFD`.
+    ///
+    /// Invalid bytes are replaced the same way as in [`chars`](#method.chars) and `Display`.
+    pub fn to_string_lossy(&self) -> String {
+        if self.is_unicode() {
+            return self.code_points().map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER)).collect();
+        }
+
+        #[cfg(feature = "encoding")]
+        return self.to_string_with(self.inner.amx().encoding());
 
         #[cfg(not(feature = "encoding"))]
-        return unsafe { String::from_utf8_unchecked(self.to_bytes()) };
+        return match String::from_utf8(self.to_bytes()) {
+            Ok(string) => string,
+            Err(_) => self.chars().collect(),
+        };
+    }
+
+    /// Convert an AMX string to a `String` with given encoding, replacing invalid bytes with `U+FFFD`.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// use samp_sdk::encoding::WINDOWS_1251;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: SendCyrillicMessage(const message[]);
+    /// fn send_cyrillic_message(amx: &Amx, message: AmxString) -> AmxResult<bool> {
+    ///     println!("{}", message.to_string_with(WINDOWS_1251));
+    ///     Ok(true)
+    /// }
+    /// ```
+    #[cfg(feature = "encoding")]
+    pub fn to_string_with(&self, encoding: &'static Encoding) -> String {
+        if self.is_unicode() {
+            return self.to_string_lossy();
+        }
+
+        match ascii_string(self.to_bytes(), encoding) {
+            Ok(string) => string,
+            Err(bytes) => encoding.decode_without_bom_handling(&bytes).0.into_owned(),
+        }
+    }
+
+    /// Convert an AMX string to a `String`.
+    ///
+    /// # Errors
+    /// Return [`DecodeError`] when the string has bytes that are invalid for UTF-8 or an encoding of the `Amx`.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: SetClanName(const name[]);
+    /// fn set_clan_name(amx: &Amx, name: AmxString) -> AmxResult<bool> {
+    ///     match name.try_to_string() {
+    ///         Ok(name) => println!("New clan name: {}", name),
+    ///         Err(err) => {
+    ///             println!("Rejected clan name {:?}: {}", err.as_bytes(), err);
+    ///             return Ok(false);
+    ///         }
+    ///     }
+    ///
+    ///     Ok(true)
+    /// }
+    /// ```
+    ///
+    /// [`DecodeError`]: struct.DecodeError.html
+    pub fn try_to_string(&self) -> Result<String, DecodeError> {
+        if self.is_unicode() {
+            return self.try_to_unicode_string();
+        }
+
+        #[cfg(feature = "encoding")]
+        return self.try_to_string_with(self.inner.amx().encoding());
+
+        #[cfg(not(feature = "encoding"))]
+        return String::from_utf8(self.to_bytes()).map_err(|err| DecodeError {
+            bytes: err.into_bytes(),
+            kind: ErrorKind::Utf8,
+        });
+    }
+
+    /// Convert an AMX string to a `String` with given encoding.
+    ///
+    /// # Errors
+    /// Return [`DecodeError`] when the string has bytes that are invalid for the encoding.
+    ///
+    /// [`DecodeError`]: struct.DecodeError.html
+    #[cfg(feature = "encoding")]
+    pub fn try_to_string_with(&self, encoding: &'static Encoding) -> Result<String, DecodeError> {
+        if self.is_unicode() {
+            return self.try_to_unicode_string();
+        }
+
+        let bytes = match ascii_string(self.to_bytes(), encoding) {
+            Ok(string) => return Ok(string),
+            Err(bytes) => bytes,
+        };
+
+        match encoding.decode_without_bom_handling_and_without_replacement(&bytes) {
+            Some(string) => Ok(string.into_owned()),
+            None => Err(DecodeError {
+                bytes,
+                kind: ErrorKind::Encoding(encoding),
+            }),
+        }
+    }
+
+    /// Returns `true` when the string keeps a Unicode code point in a cell instead of a byte.
+    ///
+    /// It's detected once the string is created: by cells bigger than a byte, or by non-ASCII bytes that
+    /// `amx_UTF8Check` doesn't accept as UTF-8 (only with the UTF-8 encoding when the `encoding` feature is on).
+    /// A string of Latin-1 chars can still look like UTF-8 bytes, so
+    /// [`try_to_unicode_string`](#method.try_to_unicode_string) should be used for scripts that always use code points.
+    #[inline]
+    pub fn is_unicode(&self) -> bool {
+        self.unicode
+    }
+
+    /// Convert a string of Unicode code points (one in a cell) to a `String`.
+    ///
+    /// # Errors
+    /// Return [`DecodeError`] when a cell isn't a valid Unicode code point.
+    ///
+    /// # Example
+    /// ```
+    /// use samp_sdk::cell::AmxString;
+    /// # use samp_sdk::amx::Amx;
+    /// # use samp_sdk::error::AmxResult;
+    ///
+    /// // native: SendEmojiMessage(const message[]);
+    /// fn send_emoji_message(amx: &Amx, message: AmxString) -> AmxResult<bool> {
+    ///     match message.try_to_unicode_string() {
+    ///         Ok(message) => println!("{}", message),
+    ///         Err(_) => return Ok(false),
+    ///     }
+    ///
+    ///     Ok(true)
+    /// }
+    /// ```
+    ///
+    /// [`DecodeError`]: struct.DecodeError.html
+    pub fn try_to_unicode_string(&self) -> Result<String, DecodeError> {
+        if is_packed(self.inner[0]) {
+            return self.try_to_string();
+        }
+
+        self.code_points()
+            .collect::<Option<String>>()
+            .ok_or_else(|| DecodeError {
+                bytes: self.to_bytes(),
+                kind: ErrorKind::CodePoint,
+            })
+    }
+
+    fn code_points(&self) -> impl Iterator<Item = Option<char>> + '_ {
+        self.inner
+            .iter()
+            .take(self.len)
+            .map(|&cell| std::char::from_u32(cell as u32))
     }
 
     /// Return a length of a string.
@@ -116,13 +392,23 @@ impl<'amx> AmxString<'amx> {
 impl<'amx> AmxCell<'amx> for AmxString<'amx> {
     fn from_raw(amx: &'amx Amx, cell: i32) -> AmxResult<AmxString<'amx>> {
         let buffer = UnsizedBuffer::from_raw(amx, cell)?;
-        let ptr = buffer.as_ptr();
-        let str_len = amx.strlen(ptr)?;
-        let buf_len = str_len + 1;
+
+        // the terminator is searched only up to the end of the heap or the stack holding the string
+        let cells = amx.cells_after(cell)?;
+        let data = unsafe { std::slice::from_raw_parts(buffer.as_ptr(), cells) };
+        let str_len = c_strlen(data).ok_or(AmxError::MemoryAccess)?;
+
+        // a packed string keeps 4 chars in a cell
+        let buf_len = if is_packed(data[0]) {
+            str_len / 4 + 1
+        } else {
+            str_len + 1
+        };
 
         Ok(AmxString {
-            inner: buffer.into_sized_buffer(buf_len),
+            inner: buffer.into_sized_buffer(buf_len)?,
             len: str_len,
+            unicode: is_unicode(amx, data, str_len),
         })
     }
 
@@ -131,12 +417,244 @@ impl<'amx> AmxCell<'amx> for AmxString<'amx> {
     }
 }
 
+impl PartialEq<str> for AmxString<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl PartialEq<&str> for AmxString<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl PartialEq for AmxString<'_> {
+    fn eq(&self, other: &AmxString) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl Eq for AmxString<'_> {}
+
+// hashed the same way as `str`: UTF-8 bytes and 0xff
+impl Hash for AmxString<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut buf = [0; 4];
+
+        for ch in self.chars() {
+            state.write(ch.encode_utf8(&mut buf).as_bytes());
+        }
+
+        state.write_u8(0xff);
+    }
+}
+
+/// An iterator over chars of an [`AmxString`], see [`AmxString::chars`].
+///
+/// [`AmxString`]: struct.AmxString.html
+/// [`AmxString::chars`]: struct.AmxString.html#method.chars
+pub struct Chars<'s, 'amx> {
+    string: &'s AmxString<'amx>,
+    pos: usize,
+    packed: bool,
+    unicode: bool,
+    #[cfg(feature = "encoding")]
+    decoder: ByteDecoder,
+}
+
+impl<'s, 'amx> Chars<'s, 'amx> {
+    fn new(string: &'s AmxString<'amx>) -> Chars<'s, 'amx> {
+        Chars {
+            string,
+            pos: 0,
+            packed: is_packed(string.inner[0]),
+            unicode: string.is_unicode(),
+            #[cfg(feature = "encoding")]
+            decoder: ByteDecoder::new(string.inner.amx().encoding()),
+        }
+    }
+
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        if self.pos < self.string.len {
+            let byte = byte_at(&self.string.inner, self.packed, self.pos);
+            self.pos += 1;
+            Some(byte)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    fn next_decoded(&mut self) -> Option<char> {
+        loop {
+            if let Some(ch) = self.decoder.pop() {
+                return Some(ch);
+            }
+
+            if self.decoder.finished {
+                return None;
+            }
+
+            let byte = self.next_byte();
+            self.decoder.push(byte);
+        }
+    }
+
+    // UTF-8 is decoded by hand to not collect bytes from cells, invalid bytes are replaced with U+FFFD
+    #[cfg(not(feature = "encoding"))]
+    fn next_decoded(&mut self) -> Option<char> {
+        let first = self.next_byte()?;
+
+        let width = match first {
+            0x00..=0x7F => return Some(char::from(first)),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Some(char::REPLACEMENT_CHARACTER),
+        };
+
+        if self.pos + width - 1 > self.string.len {
+            return Some(char::REPLACEMENT_CHARACTER);
+        }
+
+        let mut buf = [first, 0, 0, 0];
+
+        for (offset, byte) in buf[1..width].iter_mut().enumerate() {
+            *byte = byte_at(&self.string.inner, self.packed, self.pos + offset);
+        }
+
+        match std::str::from_utf8(&buf[..width]) {
+            Ok(string) => {
+                self.pos += width - 1;
+                string.chars().next()
+            }
+            Err(_) => Some(char::REPLACEMENT_CHARACTER),
+        }
+    }
+}
+
+impl Iterator for Chars<'_, '_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.unicode {
+            if self.pos >= self.string.len {
+                return None;
+            }
+
+            let cell = self.string.inner[self.pos];
+            self.pos += 1;
+
+            Some(std::char::from_u32(cell as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        } else {
+            self.next_decoded()
+        }
+    }
+}
+
+// decodes bytes one by one with an `encoding_rs` decoder and keeps decoded UTF-8 until it's taken
+#[cfg(feature = "encoding")]
+struct ByteDecoder {
+    decoder: encoding_rs::Decoder,
+    out: [u8; 16],
+    start: usize,
+    end: usize,
+    finished: bool,
+}
+
+#[cfg(feature = "encoding")]
+impl ByteDecoder {
+    fn new(encoding: &'static Encoding) -> ByteDecoder {
+        ByteDecoder {
+            decoder: encoding.new_decoder_without_bom_handling(),
+            out: [0; 16],
+            start: 0,
+            end: 0,
+            finished: false,
+        }
+    }
+
+    fn push(&mut self, byte: Option<u8>) {
+        let (src, last) = match byte {
+            Some(ref byte) => (std::slice::from_ref(byte), false),
+            None => (&[][..], true),
+        };
+
+        let (_, _, written, _) = self.decoder.decode_to_utf8(src, &mut self.out, last);
+
+        self.start = 0;
+        self.end = written;
+        self.finished = last;
+    }
+
+    fn pop(&mut self) -> Option<char> {
+        if self.start == self.end {
+            return None;
+        }
+
+        // the decoder writes only valid UTF-8
+        let decoded = unsafe { std::str::from_utf8_unchecked(&self.out[self.start..self.end]) };
+        let ch = decoded.chars().next()?;
+        self.start += ch.len_utf8();
+
+        Some(ch)
+    }
+}
+
 impl fmt::Display for AmxString<'_> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.to_string())
+        self.chars().try_for_each(|ch| fmt.write_char(ch))
     }
 }
 
+/// An error returned by [`AmxString::try_to_string`] when a string can't be decoded.
+///
+/// [`AmxString::try_to_string`]: struct.AmxString.html#method.try_to_string
+#[derive(Debug)]
+pub struct DecodeError {
+    bytes: Vec<u8>,
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    #[cfg(not(feature = "encoding"))]
+    Utf8,
+    #[cfg(feature = "encoding")]
+    Encoding(&'static Encoding),
+    CodePoint,
+}
+
+impl DecodeError {
+    /// Return raw bytes of the string, see [`AmxString::to_bytes`].
+    ///
+    /// [`AmxString::to_bytes`]: struct.AmxString.html#method.to_bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Convert the error into raw bytes of the string.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            #[cfg(not(feature = "encoding"))]
+            ErrorKind::Utf8 => write!(fmt, "string is invalid UTF-8"),
+            #[cfg(feature = "encoding")]
+            ErrorKind::Encoding(encoding) => write!(fmt, "string is invalid for {} encoding", encoding.name()),
+            ErrorKind::CodePoint => write!(fmt, "string has an invalid Unicode code point"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 /// Fill a buffer with given string.
 ///
 /// # Example
@@ -162,12 +680,7 @@ impl fmt::Display for AmxString<'_> {
 /// # Errors
 /// Return `AmxError::General` when length of string bytes is more than size of the buffer.
 pub fn put_in_buffer(buffer: &mut Buffer, string: &str) -> AmxResult<()> {
-    #[cfg(feature = "encoding")]
-    let bytes = encoding::get().encode(string).0;
-    
-    #[cfg(not(feature = "encoding"))]
-    let bytes = std::borrow::Cow::from(string.as_bytes());
-
+    let bytes = encode(buffer.amx(), string);
     let bytes = bytes.as_ref();
 
     if bytes.len() >= buffer.len() {
@@ -182,3 +695,510 @@ pub fn put_in_buffer(buffer: &mut Buffer, string: &str) -> AmxResult<()> {
 
     Ok(())
 }
+
+/// Fill a buffer with given string in the packed format (`buffer[] char` in Pawn).
+///
+/// # Example
+/// ```rust,no_run
+/// use samp_sdk::cell::string;
+/// # use samp_sdk::error::AmxResult;
+/// # use samp_sdk::amx::Amx;
+///
+/// # fn main() -> AmxResult<()> {
+/// # let amx = Amx::new(std::ptr::null_mut(), 0);
+/// let allocator = amx.allocator();
+/// let mut buffer = allocator.allot_buffer(4)?; // 16 chars, including the terminating zero.
+/// string::put_in_buffer_packed(&mut buffer, "Hello, world!")?;
+///
+/// #   Ok(())
+/// # }
+/// ```
+/// # Errors
+/// Return `AmxError::General` when the packed string with the terminating zero doesn't fit the buffer.
+pub fn put_in_buffer_packed(buffer: &mut Buffer, string: &str) -> AmxResult<()> {
+    let bytes = encode(buffer.amx(), string);
+    let bytes = bytes.as_ref();
+
+    if bytes.len() / 4 + 1 > buffer.len() {
+        return Err(crate::error::AmxError::General);
+    }
+
+    write_packed(buffer, bytes);
+
+    Ok(())
+}
+
+/// Fill a buffer with Unicode code points of a string, a char in a cell.
+///
+/// # Errors
+/// Return `AmxError::General` when the string with the terminating zero doesn't fit the buffer.
+pub fn put_in_buffer_unicode(buffer: &mut Buffer, string: &str) -> AmxResult<()> {
+    if string.chars().count() >= buffer.len() {
+        return Err(crate::error::AmxError::General);
+    }
+
+    write_unicode(buffer, string);
+
+    Ok(())
+}
+
+/// An output string argument of a native (`dest[], len = sizeof dest` in Pawn).
+///
+/// A string written into it is truncated to fit the buffer and always has a terminating zero.
+///
+/// # Example
+/// ```
+/// use samp_sdk::cell::AmxStringOut;
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: GetPluginName(dest[], len = sizeof dest);
+/// // #[native(name = "GetPluginName")]
+/// fn get_plugin_name(amx: &Amx, dest: &mut AmxStringOut) -> AmxResult<usize> {
+///     let written = dest.write_str("memcached");
+///     Ok(written)
+/// }
+/// ```
+pub struct AmxStringOut<'amx> {
+    inner: Buffer<'amx>,
+    packed: bool,
+}
+
+impl<'amx> AmxStringOut<'amx> {
+    /// Create an output string over a buffer, `packed` chooses a packed (`dest[] char`) format of the string.
+    pub fn new(buffer: Buffer<'amx>, packed: bool) -> AmxStringOut<'amx> {
+        AmxStringOut {
+            inner: buffer,
+            packed,
+        }
+    }
+
+    /// Write a string into the buffer and return a count of written bytes (without the terminating zero).
+    ///
+    /// A string that doesn't fit the buffer is truncated without splitting a char.
+    pub fn write_str(&mut self, string: &str) -> usize {
+        if self.inner.is_empty() {
+            return 0;
+        }
+
+        let bytes = encode(self.inner.amx(), string);
+        let len = truncated_len(self.inner.amx(), string, bytes.as_ref(), self.capacity());
+        let bytes = &bytes[..len];
+
+        if self.packed {
+            write_packed(&mut self.inner, bytes);
+        } else {
+            for (idx, byte) in bytes.iter().enumerate() {
+                self.inner[idx] = i32::from(*byte);
+            }
+
+            self.inner[len] = 0;
+        }
+
+        len
+    }
+
+    /// Return a max count of bytes that can be written into the buffer.
+    pub fn capacity(&self) -> usize {
+        let cells = self.inner.len();
+
+        if self.packed {
+            (cells * 4).saturating_sub(1)
+        } else {
+            cells.saturating_sub(1)
+        }
+    }
+
+    /// Returns `true` when a string is written in the packed format.
+    #[inline]
+    pub fn is_packed(&self) -> bool {
+        self.packed
+    }
+
+    /// Change a format of strings written by [`write_str`].
+    ///
+    /// [`write_str`]: #method.write_str
+    #[inline]
+    pub fn set_packed(&mut self, packed: bool) {
+        self.packed = packed;
+    }
+}
+
+/// A string in a script buffer that can be edited in place, like `strcat`, `strins` and `strdel` do in Pawn.
+///
+/// Edits keep the terminating zero and fail with `AmxError::General` when a result doesn't fit the buffer.
+/// Indices and lengths are in bytes of the encoded string, Unicode strings (a code point in a cell) aren't supported.
+///
+/// # Example
+/// ```
+/// use samp_sdk::cell::{AmxStringMut, UnsizedBuffer};
+/// # use samp_sdk::amx::Amx;
+/// # use samp_sdk::error::AmxResult;
+///
+/// // native: AddClanTag(name[], size = sizeof name);
+/// fn add_clan_tag(amx: &Amx, name: UnsizedBuffer, size: usize) -> AmxResult<bool> {
+///     let mut name = AmxStringMut::new(name, size)?;
+///
+///     if name.as_amx_string().starts_with("[RU]") {
+///         return Ok(false);
+///     }
+///
+///     name.insert(0, "[RU]")?;
+///     Ok(true)
+/// }
+/// ```
+pub struct AmxStringMut<'amx> {
+    inner: Buffer<'amx>,
+    len: usize,
+    packed: bool,
+}
+
+impl<'amx> AmxStringMut<'amx> {
+    /// Create a string over a buffer of `size` cells, a format of the string is detected by its content.
+    ///
+    /// # Errors
+    /// Return `AmxError::MemoryAccess` when the buffer is out of the AMX data
+    /// and `AmxError::General` when there is no terminating zero in the buffer.
+    pub fn new(buffer: UnsizedBuffer<'amx>, size: usize) -> AmxResult<AmxStringMut<'amx>> {
+        let buffer = buffer.into_sized_buffer(size)?;
+        let packed = buffer.first().is_some_and(|&cell| is_packed(cell));
+
+        AmxStringMut::from_buffer(buffer, packed)
+    }
+
+    /// Create a packed string (`dest[] char` in Pawn) over a buffer of `size` cells.
+    ///
+    /// # Errors
+    /// The same as [`new`](#method.new).
+    pub fn new_packed(buffer: UnsizedBuffer<'amx>, size: usize) -> AmxResult<AmxStringMut<'amx>> {
+        let buffer = buffer.into_sized_buffer(size)?;
+        AmxStringMut::from_buffer(buffer, true)
+    }
+
+    fn from_buffer(inner: Buffer<'amx>, packed: bool) -> AmxResult<AmxStringMut<'amx>> {
+        let len = if packed {
+            (0..inner.len() * 4).find(|&idx| byte_at(&inner, true, idx) == 0)
+        } else {
+            inner.iter().position(|&cell| cell == 0)
+        };
+
+        Ok(AmxStringMut {
+            len: len.ok_or(AmxError::General)?,
+            inner,
+            packed,
+        })
+    }
+
+    /// Return a length of the string in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a max length of the string in bytes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        if self.packed {
+            self.inner.len() * 4 - 1
+        } else {
+            self.inner.len() - 1
+        }
+    }
+
+    /// Returns `true` when the string is packed.
+    #[inline]
+    pub fn is_packed(&self) -> bool {
+        self.packed
+    }
+
+    /// Return an iterator over bytes of the string.
+    pub fn bytes(&self) -> impl ExactSizeIterator<Item = u8> + '_ {
+        (0..self.len).map(move |idx| byte_at(&self.inner, self.packed, idx))
+    }
+
+    /// Return a read-only view of the string to decode or compare it.
+    pub fn as_amx_string(&self) -> AmxString<'_> {
+        let amx = self.inner.amx();
+        let ptr = self.inner.as_ptr() as *mut i32;
+
+        // the view borrows `self`, so the string can't be changed while it's alive
+        unsafe {
+            let reference = Ref::new(amx, self.inner.as_cell(), ptr);
+
+            AmxString {
+                inner: Buffer::new_unchecked(reference, self.inner.len()),
+                len: self.len,
+                unicode: is_unicode(amx, &self.inner, self.len),
+            }
+        }
+    }
+
+    /// Append a string to the end.
+    ///
+    /// # Errors
+    /// Return `AmxError::General` when the result doesn't fit the buffer.
+    pub fn push_str(&mut self, string: &str) -> AmxResult<()> {
+        let bytes = encode(self.inner.amx(), string);
+        self.insert_bytes(self.len, bytes.as_ref())
+    }
+
+    /// Insert a string at a byte index.
+    ///
+    /// # Errors
+    /// Return `AmxError::Bounds` when `idx` is bigger than the length
+    /// and `AmxError::General` when the result doesn't fit the buffer.
+    pub fn insert(&mut self, idx: usize, string: &str) -> AmxResult<()> {
+        if idx > self.len {
+            return Err(AmxError::Bounds);
+        }
+
+        let bytes = encode(self.inner.amx(), string);
+        self.insert_bytes(idx, bytes.as_ref())
+    }
+
+    /// Shorten the string to `len` bytes, does nothing when `len` isn't less than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.set_len(len);
+        }
+    }
+
+    /// Make the string empty.
+    pub fn clear(&mut self) {
+        self.set_len(0);
+    }
+
+    /// Replace all matches of `from` with `to` and return a count of replaced matches.
+    ///
+    /// # Errors
+    /// Return `AmxError::General` when the result doesn't fit the buffer, the string isn't changed in this case.
+    pub fn replace(&mut self, from: &str, to: &str) -> AmxResult<usize> {
+        let amx = self.inner.amx();
+        let (from, to) = (encode(amx, from), encode(amx, to));
+        let (from, to) = (from.as_ref(), to.as_ref());
+
+        if from.is_empty() {
+            return Ok(0);
+        }
+
+        let bytes: Vec<u8> = self.bytes().collect();
+        let mut replaced = Vec::with_capacity(bytes.len());
+        let mut count = 0;
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            if bytes[pos..].starts_with(from) {
+                replaced.extend_from_slice(to);
+                pos += from.len();
+                count += 1;
+            } else {
+                replaced.push(bytes[pos]);
+                pos += 1;
+            }
+        }
+
+        if replaced.len() > self.capacity() {
+            return Err(AmxError::General);
+        }
+
+        for (idx, &byte) in replaced.iter().enumerate() {
+            self.set_byte(idx, byte);
+        }
+
+        self.set_len(replaced.len());
+
+        Ok(count)
+    }
+
+    fn insert_bytes(&mut self, idx: usize, bytes: &[u8]) -> AmxResult<()> {
+        let len = self.len + bytes.len();
+
+        if len > self.capacity() {
+            return Err(AmxError::General);
+        }
+
+        for pos in (idx..self.len).rev() {
+            let byte = byte_at(&self.inner, self.packed, pos);
+            self.set_byte(pos + bytes.len(), byte);
+        }
+
+        for (offset, &byte) in bytes.iter().enumerate() {
+            self.set_byte(idx + offset, byte);
+        }
+
+        self.set_len(len);
+
+        Ok(())
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.set_byte(len, 0);
+    }
+
+    fn set_byte(&mut self, idx: usize, byte: u8) {
+        if self.packed {
+            let shift = (3 - idx % 4) * 8;
+            let cell = &mut self.inner[idx / 4];
+            let value = (*cell as u32 & !(0xFF << shift)) | (u32::from(byte) << shift);
+
+            *cell = value as i32;
+        } else {
+            self.inner[idx] = i32::from(byte);
+        }
+    }
+}
+
+impl<'amx> AmxCell<'amx> for AmxStringMut<'amx> {
+    #[inline]
+    fn as_cell(&self) -> i32 {
+        self.inner.as_cell()
+    }
+}
+
+impl fmt::Display for AmxStringMut<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.as_amx_string().fmt(fmt)
+    }
+}
+
+// an unpacked string keeps code points when a cell is bigger than a byte, or when its non-ASCII bytes
+// aren't UTF-8 (checked by `amx_UTF8Check`) and can be only Latin-1 code points
+fn is_unicode(amx: &Amx, cells: &[i32], len: usize) -> bool {
+    let cells = &cells[..len.min(cells.len())];
+
+    if cells.is_empty() || is_packed(cells[0]) {
+        return false;
+    }
+
+    if cells.iter().any(|&cell| cell > MAX_BYTE) {
+        return true;
+    }
+
+    if cells.iter().all(|&cell| cell < 0x80) {
+        return false;
+    }
+
+    // bytes of a single byte encoding aren't code points
+    #[cfg(feature = "encoding")]
+    {
+        if amx.encoding() != encoding_rs::UTF_8 {
+            return false;
+        }
+    }
+
+    let bytes: Vec<u8> = cells.iter().map(|&cell| cell as u8).collect();
+
+    match CString::new(bytes) {
+        Ok(string) => amx.utf8_check(&string).is_err(),
+        Err(_) => false,
+    }
+}
+
+// chars of an unpacked string are less than `MAX_UNPACKED`, a packed one has the first char in the highest byte
+#[inline]
+fn is_packed(cell: i32) -> bool {
+    cell as u32 > MAX_UNPACKED as u32
+}
+
+// ASCII is the same in UTF-8 and ASCII-compatible encodings, so bytes are reused without decoding
+#[cfg(feature = "encoding")]
+fn ascii_string(bytes: Vec<u8>, encoding: &'static Encoding) -> Result<String, Vec<u8>> {
+    if encoding.is_ascii_compatible() && bytes.is_ascii() {
+        Ok(unsafe { String::from_utf8_unchecked(bytes) })
+    } else {
+        Err(bytes)
+    }
+}
+
+// encode a string with an encoding of the `Amx`
+#[cfg_attr(not(feature = "encoding"), allow(unused_variables))]
+pub(crate) fn encode<'a>(amx: &Amx, string: &'a str) -> Cow<'a, [u8]> {
+    #[cfg(feature = "encoding")]
+    return amx.encoding().encode(string).0;
+
+    #[cfg(not(feature = "encoding"))]
+    return Cow::from(string.as_bytes());
+}
+
+// length of bytes that fits `max` without splitting a char, encoded chars are counted one by one
+#[cfg_attr(not(feature = "encoding"), allow(unused_variables))]
+fn truncated_len(amx: &Amx, string: &str, bytes: &[u8], max: usize) -> usize {
+    if bytes.len() <= max {
+        return bytes.len();
+    }
+
+    #[cfg(feature = "encoding")]
+    {
+        let encoding = amx.encoding();
+        let mut char_buf = [0; 4];
+        let mut len = 0;
+
+        for ch in string.chars() {
+            let char_len = encoding.encode(ch.encode_utf8(&mut char_buf)).0.len();
+
+            if len + char_len > max {
+                break;
+            }
+
+            len += char_len;
+        }
+
+        len
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    (0..=max).rev().find(|&idx| string.is_char_boundary(idx)).unwrap_or(0)
+}
+
+// a length of a nul-terminated string in chars, `None` when there is no terminator in the cells
+fn c_strlen(cells: &[i32]) -> Option<usize> {
+    if is_packed(*cells.first()?) {
+        (0..cells.len() * 4).find(|&idx| byte_at(cells, true, idx) == 0)
+    } else {
+        cells.iter().position(|&cell| cell == 0)
+    }
+}
+
+// a byte of a packed string is in a cell with the first byte in the highest one
+#[inline]
+fn byte_at(cells: &[i32], packed: bool, idx: usize) -> u8 {
+    if packed {
+        (cells[idx / 4] >> ((3 - idx % 4) * 8)) as u8
+    } else {
+        cells[idx] as u8
+    }
+}
+
+/// Write chars into cells with the terminating zero, return a count of chars.
+fn write_unicode(buffer: &mut [i32], string: &str) -> usize {
+    let mut len = 0;
+
+    for (cell, ch) in buffer.iter_mut().zip(string.chars()) {
+        *cell = ch as i32;
+        len += 1;
+    }
+
+    buffer[len] = 0;
+    len
+}
+
+/// Pack bytes into cells (4 bytes per cell, the first byte is the highest one) with the terminating zero.
+pub(crate) fn write_packed(buffer: &mut [i32], bytes: &[u8]) {
+    let cells = bytes.len() / 4 + 1;
+
+    for (idx, cell) in buffer.iter_mut().take(cells).enumerate() {
+        let mut packed = [0u8; 4];
+
+        for (offset, byte) in packed.iter_mut().enumerate() {
+            *byte = bytes.get(idx * 4 + offset).cloned().unwrap_or(0);
+        }
+
+        *cell = i32::from_be_bytes(packed);
+    }
+}
@@ -1,17 +1,37 @@
 //! String encoding.
-pub use encoding_rs::{WINDOWS_1251, WINDOWS_1252};
-use encoding_rs::Encoding;
+//!
+//! Every [`Amx`] can have its own encoding (see [`Amx::set_encoding`]), the default encoding is used otherwise.
+//!
+//! [`Amx`]: ../amx/struct.Amx.html
+//! [`Amx::set_encoding`]: ../amx/struct.Amx.html#method.set_encoding
+use std::sync::atomic::{AtomicPtr, Ordering};
 
-static mut DEFAULT_ENCODING: &Encoding = WINDOWS_1252;
+pub use encoding_rs::{Encoding, WINDOWS_1251, WINDOWS_1252};
 
+static DEFAULT_ENCODING: AtomicPtr<Encoding> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Set an encoding used by an `Amx` that has no own encoding.
 pub fn set_default_encoding(encoding: &'static Encoding) {
-    unsafe {
-        DEFAULT_ENCODING = encoding;
-    }
+    DEFAULT_ENCODING.store(encoding as *const Encoding as *mut Encoding, Ordering::Release);
 }
 
-pub(crate) fn get() -> &'static Encoding {
-    unsafe {
-        DEFAULT_ENCODING
-    }
-}
\ No newline at end of file
+/// Return the default encoding, `WINDOWS_1252` if it isn't set.
+pub fn default_encoding() -> &'static Encoding {
+    let encoding = DEFAULT_ENCODING.load(Ordering::Acquire);
+
+    // the pointer is either null or made from a `&'static Encoding`
+    unsafe { encoding.as_ref().unwrap_or(WINDOWS_1252) }
+}
+
+/// Find an encoding by its label, like `"windows-1251"` or `"cp1251"`.
+///
+/// # Example
+/// ```
+/// use samp_sdk::encoding;
+///
+/// let encoding = encoding::for_label("cp1251");
+/// assert_eq!(encoding, Some(encoding::WINDOWS_1251));
+/// ```
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.as_bytes())
+}
@@ -28,7 +28,7 @@
 ///
 /// // native:CallPublic(const publicname[], const string[], buffer[], length, &someref);
 /// fn call_public(amx: &Amx, pub_name: AmxString, string: AmxString, buffer: UnsizedBuffer, size: usize, reference: Ref<usize>) -> AmxResult<bool> {
-///     let buffer = buffer.into_sized_buffer(size);
+///     let buffer = buffer.into_sized_buffer(size)?;
 ///     let public_name = pub_name.to_string();
 ///
 ///     exec_public!(amx, &public_name, string, buffer, reference);
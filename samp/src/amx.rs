@@ -1,8 +1,16 @@
 //! Core Amx types with additional functions.
 pub use samp_sdk::amx::*;
+use samp_sdk::cell::AmxCell;
+use samp_sdk::consts::AmxExecIdx;
+use samp_sdk::error::AmxResult;
 use samp_sdk::raw::types::AMX;
 
-use crate::runtime::Runtime;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Once;
+
+use crate::runtime::{self, Runtime, RuntimeError};
 
 /// Get a reference to an `Amx` by given `AmxIdent`.
 ///
@@ -10,48 +18,241 @@ use crate::runtime::Runtime;
 /// ```
 /// use samp::prelude::*;
 /// use samp::exec_public;
-/// # use samp::native;
+/// # use samp::{native, natives};
 /// # use samp::amx::AmxIdent;
 ///
 /// # struct Plugin {
-/// #       subscribtions: std::collections::HashMap<String, Vec<AmxIdent>>,
+/// #       owner: Option<AmxIdent>,
 /// # }
 /// #
 /// # impl SampPlugin for Plugin {}
 /// #
+/// # #[natives]
 /// # impl Plugin {
 ///
-/// #[native(name = "SubscribeToEvent")]
-/// fn subscribe(&mut self, amx: &Amx, event_name: AmxString) -> AmxResult<bool> {
-///     let event_name = event_name.to_string();
-///     let subs = self.subscribtions.entry(event_name).or_insert(vec![]);
-///     subs.push(amx.ident());
-///
+/// #[native(name = "TakeOwnership")]
+/// fn take_ownership(&mut self, amx: &Amx) -> AmxResult<bool> {
+///     self.owner = Some(amx.ident());
 ///     Ok(true)
 /// }
 ///
-/// fn publish(&self, event_name: &str) {
-///     if let Some(subs) = self.subscribtions.get(event_name) {
-///         for ident in subs {
-///             if let Some(amx) = samp::amx::get(*ident) {
-///                 let _ = exec_public!(amx, event_name);
-///             }
-///         }
+/// fn notify_owner(&self) {
+///     if let Some(amx) = self.owner.and_then(samp::amx::get) {
+///         let _ = exec_public!(amx, "OnOwnerNotified");
 ///     }
 /// }
 ///
 /// # }
 /// ```
+///
+/// To call a public in every script use [`call_everywhere`](fn.call_everywhere.html).
+///
+/// # Notes
+/// `None` is returned when the function is called not from the server thread, the first such call is logged.
 #[inline]
 pub fn get<'a>(ident: AmxIdent) -> Option<&'a Amx> {
-    let rt = Runtime::get();
-    rt.amx_list().get(&ident)
+    let rt = server_runtime("get")?;
+    rt.get_amx(ident)
 }
 
 #[inline]
-pub fn add<'a>(amx: *mut AMX) {
-    let rt = Runtime::get();
-    rt.insert_amx(amx);
+pub fn add<'a>(amx: *mut AMX) -> Option<&'a Amx> {
+    let rt = server_runtime("add")?;
+    Some(rt.insert_amx(amx))
+}
+
+// a call from another thread is a bug in a plugin, so it's logged once instead of looking like an unknown script
+fn server_runtime(caller: &str) -> Option<&'static Runtime> {
+    static WRONG_THREAD: Once = Once::new();
+
+    match Runtime::get() {
+        Ok(rt) => Some(rt),
+        Err(err @ RuntimeError::WrongThread) => {
+            WRONG_THREAD.call_once(|| runtime::log(format_args!("error: samp::amx::{} is {}", caller, err)));
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Get all loaded scripts in the order they were loaded.
+///
+/// An empty list is returned when the function is called not from the server thread.
+///
+/// # Example
+/// ```
+/// use samp::prelude::*;
+/// use samp::amx::ScriptKind;
+///
+/// fn count_filterscripts() -> usize {
+///     samp::amx::all()
+///         .into_iter()
+///         .filter(|amx| amx.kind() == ScriptKind::Filterscript)
+///         .count()
+/// }
+/// ```
+pub fn all<'a>() -> Vec<&'a Amx> {
+    match Runtime::get() {
+        Ok(rt) => rt.all_amx(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Get the loaded gamemode.
+pub fn gamemode<'a>() -> Option<&'a Amx> {
+    all().into_iter().find(|amx| amx.kind() == ScriptKind::Gamemode)
+}
+
+/// Get loaded filterscripts in load order.
+pub fn filterscripts<'a>() -> Vec<&'a Amx> {
+    all()
+        .into_iter()
+        .filter(|amx| amx.kind() == ScriptKind::Filterscript)
+        .collect()
+}
+
+/// Call a public in every loaded script that has it, in load order, like `CallRemoteFunction`.
+///
+/// Arguments are allocated in every script separately. `aggregate` tells when to stop,
+/// results of called scripts are returned in call order. An error of one script doesn't stop the call.
+///
+/// # Example
+/// ```
+/// use samp::amx::{Aggregate, PublicArg};
+///
+/// // forward OnPluginEvent(const name[], value);
+/// fn notify(name: &str, value: i32) -> bool {
+///     let args = [PublicArg::from(name), PublicArg::from(value)];
+///     let results = samp::amx::call_everywhere("OnPluginEvent", &args, Aggregate::FirstNonZero);
+///
+///     // a script has handled the event
+///     matches!(results.last(), Some((_, Ok(value))) if *value != 0)
+/// }
+/// ```
+pub fn call_everywhere(name: &str, args: &[PublicArg], aggregate: Aggregate) -> Vec<(AmxIdent, AmxResult<i32>)> {
+    // a public can unload a script, so scripts are looked up again before every call
+    let idents: Vec<AmxIdent> = all().into_iter().map(AmxExt::ident).collect();
+    let mut results = Vec::new();
+
+    for ident in idents {
+        let amx = match get(ident) {
+            Some(amx) => amx,
+            None => continue,
+        };
+
+        let index = match amx.find_public(name) {
+            Ok(index) => index,
+            Err(_) => continue,
+        };
+
+        let result = exec_with_args(amx, index, args);
+        let stop = match (&result, aggregate) {
+            (Ok(value), Aggregate::FirstNonZero) => *value != 0,
+            (Ok(value), Aggregate::StopOnZero) => *value == 0,
+            _ => false,
+        };
+
+        results.push((ident, result));
+
+        if stop {
+            break;
+        }
+    }
+
+    results
+}
+
+fn exec_with_args(amx: &Amx, index: AmxExecIdx, args: &[PublicArg]) -> AmxResult<i32> {
+    let allocator = amx.allocator();
+
+    // everything is allocated before the first push, the allocator frees the heap on an error
+    let cells = args
+        .iter()
+        .map(|arg| match *arg {
+            PublicArg::Cell(value) => Ok(value),
+            PublicArg::String(string) => allocator.allot_string(string).map(|string| string.as_cell()),
+            PublicArg::Array(array) => allocator.allot_array(array).map(|array| array.as_cell()),
+        })
+        .collect::<AmxResult<Vec<i32>>>()?;
+
+    let raw = amx.amx().as_ptr();
+    let (stk, paramcount) = unsafe { ((*raw).stk, (*raw).paramcount) };
+
+    // the last argument is pushed first
+    if let Err(err) = cells.iter().rev().try_for_each(|&cell| amx.push(cell)) {
+        // pushed arguments are dropped, so the stack is the same as before the call
+        unsafe {
+            (*raw).stk = stk;
+            (*raw).paramcount = paramcount;
+        }
+
+        return Err(err);
+    }
+
+    amx.exec(index)
+}
+
+/// When [`call_everywhere`](fn.call_everywhere.html) stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Call every script.
+    CollectAll,
+    /// Stop after a script returns non-zero, like an event that is handled.
+    FirstNonZero,
+    /// Stop after a script returns zero, like `OnPlayerText` that shouldn't be passed further.
+    StopOnZero,
+}
+
+/// An argument of a public called by [`call_everywhere`](fn.call_everywhere.html).
+#[derive(Debug, Clone, Copy)]
+pub enum PublicArg<'a> {
+    /// A value of a cell, `f32` and `bool` are converted by `AmxCell`.
+    Cell(i32),
+    /// A string allocated in every script.
+    String(&'a str),
+    /// An array allocated in every script.
+    Array(&'a [i32]),
+}
+
+macro_rules! public_arg_from_cell {
+    ($($type:ty),*) => {
+        $(
+            impl From<$type> for PublicArg<'_> {
+                fn from(value: $type) -> Self {
+                    PublicArg::Cell(AmxCell::as_cell(&value))
+                }
+            }
+        )*
+    };
+}
+
+public_arg_from_cell!(i32, u32, usize, f32, bool);
+
+impl<'a> From<&'a str> for PublicArg<'a> {
+    fn from(string: &'a str) -> Self {
+        PublicArg::String(string)
+    }
+}
+
+impl<'a> From<&'a String> for PublicArg<'a> {
+    fn from(string: &'a String) -> Self {
+        PublicArg::String(string)
+    }
+}
+
+impl<'a> From<&'a [i32]> for PublicArg<'a> {
+    fn from(array: &'a [i32]) -> Self {
+        PublicArg::Array(array)
+    }
+}
+
+/// A kind of a script, see [`AmxExt::kind`](trait.AmxExt.html#tymethod.kind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptKind {
+    Gamemode,
+    Filterscript,
+    /// Neither of them, like an AMX created by another plugin.
+    Unknown,
 }
 
 /// An unique identifier of an `Amx` instance.
@@ -68,6 +269,13 @@ impl From<*mut AMX> for AmxIdent {
     }
 }
 
+impl From<&Amx> for AmxIdent {
+    #[inline]
+    fn from(amx: &Amx) -> AmxIdent {
+        amx.ident()
+    }
+}
+
 /// Extended functional of an `Amx`.
 pub trait AmxExt {
     /// Get an identifier of an `Amx`.
@@ -75,11 +283,12 @@ pub trait AmxExt {
     /// # Example
     /// ```
     /// use samp::prelude::*;
-    /// # use samp::native;
+    /// # use samp::{native, natives};
     /// # struct Plugin;
     /// #
     /// # impl SampPlugin for Plugin {}
     /// #
+    /// # #[natives]
     /// # impl Plugin {
     ///
     /// #[native(name = "A")]
@@ -92,6 +301,13 @@ pub trait AmxExt {
     /// # }
     /// ```
     fn ident(&self) -> AmxIdent;
+
+    /// Find out whether a script is a gamemode or a filterscript.
+    ///
+    /// A script is a gamemode when it has `OnGameModeInit` or `main` (the server doesn't run a gamemode without it),
+    /// so a filterscript that includes an `OnGameModeInit` hook is still a gamemode. Otherwise a script is
+    /// a filterscript when it has `OnFilterScriptInit`.
+    fn kind(&self) -> ScriptKind;
 }
 
 impl AmxExt for Amx {
@@ -99,4 +315,424 @@ impl AmxExt for Amx {
     fn ident(&self) -> AmxIdent {
         self.amx().as_ptr().into()
     }
+
+    fn kind(&self) -> ScriptKind {
+        if self.find_public("OnGameModeInit").is_ok() {
+            return ScriptKind::Gamemode;
+        }
+
+        // an entry point of `main`, -1 when there is no `main`
+        let main = unsafe { self.header().as_ref().cip };
+
+        if main >= 0 {
+            ScriptKind::Gamemode
+        } else if self.find_public("OnFilterScriptInit").is_ok() {
+            ScriptKind::Filterscript
+        } else {
+            ScriptKind::Unknown
+        }
+    }
+}
+
+/// A map with a value for every script, a value is dropped when its script is unloaded.
+///
+/// A map registers itself in the runtime when it's used first time on the server thread.
+/// When a script is unloaded inside of a native while a value of the map is borrowed,
+/// the value is dropped on the next access to the map.
+///
+/// # Example
+/// ```
+/// use samp::prelude::*;
+/// use samp::amx::AmxMap;
+/// # use samp::{native, natives};
+///
+/// struct Plugin {
+///     subscriptions: AmxMap<Vec<String>>,
+/// }
+///
+/// # impl SampPlugin for Plugin {}
+/// #
+/// # #[natives]
+/// # impl Plugin {
+/// #[native(name = "SubscribeToEvent")]
+/// fn subscribe(&mut self, amx: &Amx, event_name: AmxString) -> AmxResult<bool> {
+///     self.subscriptions.entry(amx).push(event_name.to_string());
+///     Ok(true)
+/// }
+///
+/// fn publish(&self, event_name: &str) {
+///     for ident in self.subscriptions.idents() {
+///         let subscribed = match self.subscriptions.get(ident) {
+///             Some(events) => events.iter().any(|event| event == event_name),
+///             None => false,
+///         };
+///
+///         if let (true, Some(amx)) = (subscribed, samp::amx::get(ident)) {
+///             let _ = samp::exec_public!(amx, event_name);
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct AmxMap<T: 'static> {
+    shared: Rc<AmxMapShared<T>>,
+    registered: Cell<bool>,
+}
+
+struct AmxMapShared<T> {
+    entries: RefCell<HashMap<AmxIdent, T>>,
+    // scripts unloaded while `entries` was borrowed
+    unloaded: RefCell<Vec<AmxIdent>>,
+}
+
+/// A callback of the runtime called when a script is unloaded.
+pub(crate) trait AmxUnloadHook {
+    fn amx_unloaded(&self, ident: AmxIdent);
+}
+
+impl<T> AmxUnloadHook for AmxMapShared<T> {
+    fn amx_unloaded(&self, ident: AmxIdent) {
+        let value = match self.entries.try_borrow_mut() {
+            Ok(mut entries) => entries.remove(&ident),
+            Err(_) => {
+                self.unloaded.borrow_mut().push(ident);
+                None
+            }
+        };
+
+        // a value is dropped out of the borrow, its `Drop` can't see the map borrowed
+        drop(value);
+    }
+}
+
+impl<T: 'static> AmxMap<T> {
+    /// Create an empty map.
+    pub fn new() -> AmxMap<T> {
+        AmxMap {
+            shared: Rc::new(AmxMapShared {
+                entries: RefCell::new(HashMap::new()),
+                unloaded: RefCell::new(Vec::new()),
+            }),
+            registered: Cell::new(false),
+        }
+    }
+
+    /// Get a value of a script, it's created by `T::default()` if there is no value yet.
+    ///
+    /// # Panics
+    /// Panics when the map is already borrowed, like `RefCell::borrow_mut`.
+    pub fn entry<K: Into<AmxIdent>>(&self, amx: K) -> RefMut<'_, T>
+    where
+        T: Default,
+    {
+        self.entry_with(amx, T::default)
+    }
+
+    /// Get a value of a script, it's created by `f` if there is no value yet.
+    ///
+    /// # Panics
+    /// Panics when the map is already borrowed.
+    pub fn entry_with<K: Into<AmxIdent>, F: FnOnce() -> T>(&self, amx: K, f: F) -> RefMut<'_, T> {
+        let ident = amx.into();
+
+        RefMut::map(self.entries_mut(), |entries| entries.entry(ident).or_insert_with(f))
+    }
+
+    /// Get a value of a script.
+    ///
+    /// # Panics
+    /// Panics when the map is mutably borrowed.
+    pub fn get<K: Into<AmxIdent>>(&self, amx: K) -> Option<Ref<'_, T>> {
+        let ident = amx.into();
+        self.sync();
+
+        Ref::filter_map(self.shared.entries.borrow(), |entries| entries.get(&ident)).ok()
+    }
+
+    /// Get a mutable value of a script.
+    ///
+    /// # Panics
+    /// Panics when the map is already borrowed.
+    pub fn get_mut<K: Into<AmxIdent>>(&self, amx: K) -> Option<RefMut<'_, T>> {
+        let ident = amx.into();
+
+        RefMut::filter_map(self.entries_mut(), |entries| entries.get_mut(&ident)).ok()
+    }
+
+    /// Set a value of a script, return a previous one.
+    pub fn insert<K: Into<AmxIdent>>(&self, amx: K, value: T) -> Option<T> {
+        self.entries_mut().insert(amx.into(), value)
+    }
+
+    /// Remove a value of a script.
+    pub fn remove<K: Into<AmxIdent>>(&self, amx: K) -> Option<T> {
+        self.entries_mut().remove(&amx.into())
+    }
+
+    /// Return identifiers of all scripts that have a value.
+    pub fn idents(&self) -> Vec<AmxIdent> {
+        self.sync();
+        self.shared.entries.borrow().keys().copied().collect()
+    }
+
+    /// Return an amount of values.
+    pub fn len(&self) -> usize {
+        self.sync();
+        self.shared.entries.borrow().len()
+    }
+
+    /// Return `true` when there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entries_mut(&self) -> RefMut<'_, HashMap<AmxIdent, T>> {
+        self.sync();
+        self.shared.entries.borrow_mut()
+    }
+
+    // register in the runtime and drop values of scripts unloaded while the map was borrowed
+    fn sync(&self) {
+        if !self.registered.get() {
+            if let Ok(rt) = Runtime::get() {
+                let shared: Rc<dyn AmxUnloadHook> = self.shared.clone();
+                rt.register_unload_hook(Rc::downgrade(&shared));
+                self.registered.set(true);
+            }
+        }
+
+        let unloaded: Vec<AmxIdent> = self.shared.unloaded.borrow_mut().drain(..).collect();
+
+        for ident in unloaded {
+            self.shared.amx_unloaded(ident);
+        }
+    }
+}
+
+impl<T: 'static> Default for AmxMap<T> {
+    fn default() -> AmxMap<T> {
+        AmxMap::new()
+    }
+}
+
+impl<T: std::fmt::Debug + 'static> std::fmt::Debug for AmxMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.shared.entries.borrow().iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use samp_sdk::error::AmxError;
+    use samp_sdk::exports::Exports;
+    use samp_sdk::raw::types::AMX_HEADER;
+
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    const DATA_CELLS: usize = 16;
+
+    // a heap grows from the start of data and a stack from the end, the same as in a real AMX.
+    // a physical address is a 32-bit cell, so tests don't write to allocated memory on 64-bit hosts.
+    extern "C" fn allot(amx: *mut AMX, cells: i32, amx_addr: *mut i32, phys_addr: *mut i32) -> i32 {
+        unsafe {
+            let size = cells * 4;
+
+            if (*amx).hea + size > (*amx).stk {
+                return AmxError::Memory as i32;
+            }
+
+            *amx_addr = (*amx).hea;
+            *phys_addr = (*amx).data.offset((*amx).hea as isize) as i32;
+            (*amx).hea += size;
+        }
+
+        0
+    }
+
+    extern "C" fn push(amx: *mut AMX, value: i32) -> i32 {
+        unsafe {
+            if (*amx).stk - 4 < (*amx).hea {
+                return AmxError::StackError as i32;
+            }
+
+            (*amx).stk -= 4;
+            *((*amx).data.offset((*amx).stk as isize) as *mut i32) = value;
+            (*amx).paramcount += 1;
+        }
+
+        0
+    }
+
+    extern "C" fn exec(amx: *mut AMX, retval: *mut i32, _index: i32) -> i32 {
+        unsafe {
+            *retval = (*amx).paramcount;
+            (*amx).stk += (*amx).paramcount * 4;
+            (*amx).paramcount = 0;
+        }
+
+        0
+    }
+
+    thread_local! {
+        // publics of a script made by a test
+        static PUBLICS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn find_public(_: *mut AMX, name: *const c_char, index: *mut i32) -> i32 {
+        let name = unsafe { CStr::from_ptr(name) }.to_str().unwrap_or_default();
+
+        PUBLICS.with(|publics| match publics.borrow().iter().position(|&public| public == name) {
+            Some(idx) => {
+                unsafe { *index = idx as i32 };
+                0
+            }
+            None => AmxError::NotFound as i32,
+        })
+    }
+
+    extern "C" fn callback(_: *mut AMX, _: i32, _: *mut i32, _: *mut i32) -> i32 {
+        0
+    }
+
+    extern "C" fn debug(_: *mut AMX) -> i32 {
+        0
+    }
+
+    struct FakeAmx {
+        raw: Box<AMX>,
+        header: Box<AMX_HEADER>,
+        _data: Box<[i32; DATA_CELLS]>,
+        exports: Box<[usize; 44]>,
+    }
+
+    impl FakeAmx {
+        fn new() -> FakeAmx {
+            let mut data = Box::new([0; DATA_CELLS]);
+            let mut exports = Box::new([0; 44]);
+
+            exports[Exports::Allot as usize] = allot as *const () as usize;
+            exports[Exports::Push as usize] = push as *const () as usize;
+            exports[Exports::Exec as usize] = exec as *const () as usize;
+            exports[Exports::FindPublic as usize] = find_public as *const () as usize;
+
+            // no `main` until a test sets its entry point
+            let mut header = Box::new(AMX_HEADER {
+                size: 0,
+                magic: 0,
+                file_version: 0,
+                amx_version: 0,
+                flags: 0,
+                defsize: 0,
+                cod: 0,
+                dat: 0,
+                hea: 0,
+                stp: 0,
+                cip: -1,
+                publics: 0,
+                natives: 0,
+                libraries: 0,
+                pubvars: 0,
+                tags: 0,
+                nametable: 0,
+            });
+
+            let raw = Box::new(AMX {
+                base: &mut *header as *mut AMX_HEADER as *mut u8,
+                data: data.as_mut_ptr() as *mut u8,
+                callback,
+                debug,
+                cip: 0,
+                frm: 0,
+                hea: 0,
+                hlw: 0,
+                stk: DATA_CELLS as i32 * 4,
+                stp: DATA_CELLS as i32 * 4,
+                flags: 0,
+                usertags: [0; 4],
+                userdata: [ptr::null_mut(); 4],
+                error: 0,
+                paramcount: 0,
+                pri: 0,
+                alt: 0,
+                reset_stk: 0,
+                reset_hea: 0,
+                sysreq_d: 0,
+            });
+
+            FakeAmx {
+                raw,
+                header,
+                _data: data,
+                exports,
+            }
+        }
+
+        fn amx(&mut self) -> Amx {
+            Amx::new(&mut *self.raw, self.exports.as_ptr() as usize)
+        }
+
+        fn registers(&self) -> (i32, i32, i32) {
+            (self.raw.hea, self.raw.stk, self.raw.paramcount)
+        }
+
+        fn kind(&mut self, publics: &[&'static str], has_main: bool) -> ScriptKind {
+            PUBLICS.with(|list| *list.borrow_mut() = publics.to_vec());
+            self.header.cip = if has_main { 0 } else { -1 };
+
+            self.amx().kind()
+        }
+    }
+
+    #[test]
+    fn exec_pushes_args() {
+        let mut fake = FakeAmx::new();
+        let amx = fake.amx();
+        let args = [PublicArg::Cell(1), PublicArg::Cell(2), PublicArg::Cell(3)];
+
+        assert_eq!(exec_with_args(&amx, AmxExecIdx::from(0), &args).unwrap(), 3);
+        assert_eq!(fake.registers(), (0, DATA_CELLS as i32 * 4, 0));
+    }
+
+    #[test]
+    fn failed_allocation_keeps_stack() {
+        let mut fake = FakeAmx::new();
+        let amx = fake.amx();
+        let long = "a".repeat(DATA_CELLS);
+        let args = [PublicArg::String(&long), PublicArg::Cell(1), PublicArg::Cell(2)];
+
+        match exec_with_args(&amx, AmxExecIdx::from(0), &args) {
+            Err(AmxError::Memory) => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert_eq!(fake.registers(), (0, DATA_CELLS as i32 * 4, 0));
+    }
+
+    #[test]
+    fn failed_push_keeps_stack() {
+        let mut fake = FakeAmx::new();
+        let amx = fake.amx();
+        let args = [PublicArg::Cell(1); DATA_CELLS + 1];
+
+        match exec_with_args(&amx, AmxExecIdx::from(0), &args) {
+            Err(AmxError::StackError) => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert_eq!(fake.registers(), (0, DATA_CELLS as i32 * 4, 0));
+    }
+
+    #[test]
+    fn script_kind() {
+        let mut fake = FakeAmx::new();
+
+        assert_eq!(fake.kind(&["OnFilterScriptInit", "OnGameModeInit"], false), ScriptKind::Gamemode);
+        assert_eq!(fake.kind(&["OnFilterScriptInit"], true), ScriptKind::Gamemode);
+        assert_eq!(fake.kind(&["OnFilterScriptInit"], false), ScriptKind::Filterscript);
+        assert_eq!(fake.kind(&[], true), ScriptKind::Gamemode);
+        assert_eq!(fake.kind(&[], false), ScriptKind::Unknown);
+    }
 }
@@ -0,0 +1,563 @@
+//! Loading of plugin settings into a `serde` struct.
+//!
+//! Settings are read from the first existing file of `plugins/<name>.toml`, `plugins/<name>.json`
+//! and `plugins/<name>.cfg`, the last one has server.cfg-style `key value` lines.
+//! Then environment variables named `<NAME>_<KEY>` override them, a nested key is written as `a.b`
+//! in a `.cfg` file and as `A__B` in a variable.
+//!
+//! A value from a `.cfg` line or a variable is kept as a string (quotes around it are removed),
+//! a field that isn't a string parses it as a number, a boolean or a JSON array or object.
+//! Fields deserialized by [`serde::Deserializer::deserialize_any`] (like `serde_json::Value`) get the string.
+//!
+//! Every variable starting with the prefix is taken, so variables of a plugin named `chat_log` (`CHAT_LOG_PATH`)
+//! set a `log_path` key of a plugin named `chat`. Unknown keys are ignored unless the settings have
+//! `#[serde(deny_unknown_fields)]`, use [`Config::env_prefix`] to pick a prefix that isn't shared with another plugin.
+//!
+//! [`Config::env_prefix`]: struct.Config.html#method.env_prefix
+//!
+//! # Example
+//! ```rust,no_run
+//! use samp::initialize_plugin;
+//! use samp::prelude::*;
+//!
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Settings {
+//!     address: String,
+//!     #[serde(default)]
+//!     pool_size: usize,
+//! }
+//!
+//! struct MyPlugin {
+//!     settings: Settings,
+//! }
+//!
+//! impl SampPlugin for MyPlugin {
+//!     fn reload_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+//!         self.settings = samp::config::load("myplugin")?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! initialize_plugin!({
+//!     // plugins/myplugin.toml, MYPLUGIN_ADDRESS overrides `address`
+//!     let settings = samp::config::load("myplugin")?;
+//!
+//!     Ok::<_, samp::config::ConfigError>(MyPlugin { settings })
+//! });
+//! ```
+use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Load settings of a plugin with given name from the `plugins` folder and environment variables.
+///
+/// It's a shortcut for `Config::new(name).load()`.
+pub fn load<T: DeserializeOwned>(name: &str) -> Result<T, ConfigError> {
+    Config::new(name).load()
+}
+
+/// Sources of plugin settings.
+///
+/// # Example
+/// ```
+/// use samp::config::Config;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Settings {
+///     port: u16,
+///     motd: String,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = std::env::temp_dir().join("samp-config-doc");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("chat.cfg"), "port 7777\nmotd 100% fun\n")?;
+///
+/// let settings: Settings = Config::new("chat").dir(&dir).env_prefix(None).load()?;
+///
+/// assert_eq!(settings.port, 7777);
+/// assert_eq!(settings.motd, "100% fun");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    name: String,
+    dir: PathBuf,
+    env_prefix: Option<String>,
+}
+
+impl Config {
+    /// Settings of a plugin in the `plugins` folder, overridden by variables prefixed with the name in upper case.
+    pub fn new(name: &str) -> Config {
+        let env_prefix = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+
+        Config {
+            name: name.to_owned(),
+            dir: PathBuf::from("plugins"),
+            env_prefix: Some(env_prefix),
+        }
+    }
+
+    /// Set a folder with settings files.
+    pub fn dir<P: Into<PathBuf>>(mut self, dir: P) -> Config {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Set a prefix of environment variables, `None` disables overrides.
+    ///
+    /// A variable is taken when its name starts with the prefix and `_`, so the prefix shouldn't be
+    /// the beginning of a prefix of another plugin.
+    pub fn env_prefix(mut self, prefix: Option<&str>) -> Config {
+        self.env_prefix = prefix.map(str::to_owned);
+        self
+    }
+
+    /// Read all sources and deserialize the settings.
+    ///
+    /// # Errors
+    /// Return an error when a file can't be read or parsed, or when the settings don't match `T`.
+    /// Missing files aren't an error, fields of `T` that aren't set anywhere should have `#[serde(default)]`.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        let mut settings = self.read_file()?;
+
+        if let Some(prefix) = &self.env_prefix {
+            let prefix = format!("{}_", prefix);
+
+            for (name, value) in std::env::vars() {
+                if let Some(key) = name.strip_prefix(&prefix) {
+                    let key = key.to_lowercase().replace("__", ".");
+                    set_value(&mut settings, &key, &value);
+                }
+            }
+        }
+
+        T::deserialize(Lenient(Value::Object(settings))).map_err(ConfigError::Settings)
+    }
+
+    fn read_file(&self) -> Result<Map<String, Value>, ConfigError> {
+        for extension in &["toml", "json", "cfg"] {
+            let path = self.dir.join(format!("{}.{}", self.name, extension));
+
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(ConfigError::Io(path, err)),
+            };
+
+            let parsed = match *extension {
+                "toml" => toml::from_str(&text).map_err(|err| err.to_string()),
+                "json" => serde_json::from_str(&text).map_err(|err| err.to_string()),
+                _ => Ok(parse_cfg(&text)),
+            };
+
+            return parsed.map_err(|err| ConfigError::Parse(path, err));
+        }
+
+        Ok(Map::new())
+    }
+}
+
+/// An error returned when settings can't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A file exists, but it can't be read.
+    Io(PathBuf, io::Error),
+    /// A file has invalid syntax.
+    Parse(PathBuf, String),
+    /// The settings don't match a requested type.
+    Settings(serde_json::Error),
+}
+
+impl ConfigError {
+    /// Return a path of a file that caused the error.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ConfigError::Io(path, _) | ConfigError::Parse(path, _) => Some(path),
+            ConfigError::Settings(_) => None,
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "can't read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "can't parse {}: {}", path.display(), err),
+            ConfigError::Settings(err) => write!(f, "invalid settings: {}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(_, err) => Some(err),
+            ConfigError::Parse(..) => None,
+            ConfigError::Settings(err) => Some(err),
+        }
+    }
+}
+
+// `key value` lines, empty lines and lines starting with `#` or `//` are skipped
+fn parse_cfg(text: &str) -> Map<String, Value> {
+    let mut settings = Map::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let (key, value) = match line.find(char::is_whitespace) {
+            Some(idx) => (&line[..idx], line[idx..].trim_start()),
+            None => (line, ""),
+        };
+
+        set_value(&mut settings, key, value);
+    }
+
+    settings
+}
+
+fn set_value(settings: &mut Map<String, Value>, key: &str, raw: &str) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+    let mut map = settings;
+
+    for part in parts {
+        let entry = map.entry(part).or_insert_with(|| Value::Object(Map::new()));
+
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+
+        map = match entry {
+            Value::Object(inner) => inner,
+            _ => unreachable!(),
+        };
+    }
+
+    map.insert(last.to_owned(), Value::String(unquoted(raw)));
+}
+
+// a `"quoted"` value keeps its spaces and can look like a number
+fn unquoted(raw: &str) -> String {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(Value::String(string)) => string,
+        _ => raw.to_owned(),
+    }
+}
+
+/// Settings with values of `.cfg` lines and variables kept as strings, a string is parsed only when
+/// a field doesn't take a string.
+struct Lenient(Value);
+
+impl Lenient {
+    fn parsed(self) -> Value {
+        match self.0 {
+            Value::String(raw) => match serde_json::from_str::<Value>(&raw) {
+                Ok(value) if !value.is_string() => value,
+                _ => Value::String(raw),
+            },
+            value => value,
+        }
+    }
+}
+
+macro_rules! parse_string {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.parsed().$method(visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! keep_string {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.0.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    parse_string! {
+        deserialize_bool deserialize_f32 deserialize_f64
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+    }
+
+    keep_string! {
+        deserialize_char deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(items) => visitor.visit_seq(LenientSeq(items.into_iter())),
+            Value::Object(map) => visitor.visit_map(LenientMap::new(map)),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(Lenient(value)),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.parsed() {
+            Value::Array(items) => visitor.visit_seq(LenientSeq(items.into_iter())),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.parsed() {
+            Value::Object(map) => visitor.visit_map(LenientMap::new(map)),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+}
+
+struct LenientSeq(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for LenientSeq {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        self.0.next().map(|value| seed.deserialize(Lenient(value))).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct LenientMap {
+    iter: serde_json::map::IntoIter,
+    value: Option<Value>,
+}
+
+impl LenientMap {
+    fn new(map: Map<String, Value>) -> LenientMap {
+        LenientMap {
+            iter: map.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for LenientMap {
+    type Error = serde_json::Error;
+
+    // keys are strings too, so a map with number keys parses them
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Lenient(Value::String(key))).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        seed.deserialize(Lenient(self.value.take().unwrap_or(Value::Null)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse_cfg_lines() {
+        let settings = parse_cfg("# comment\n// comment\n\nport 7777\nmotd  100% fun \ndb.host localhost\nempty\n");
+
+        assert_eq!(
+            Value::Object(settings),
+            json!({
+                "port": "7777",
+                "motd": "100% fun",
+                "db": { "host": "localhost" },
+                "empty": "",
+            })
+        );
+    }
+
+    #[test]
+    fn unquoted_values() {
+        assert_eq!(unquoted("\"100% fun\""), "100% fun");
+        assert_eq!(unquoted("\"123\""), "123");
+        assert_eq!(unquoted("123"), "123");
+        assert_eq!(unquoted("\"open"), "\"open");
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Typed {
+        name: String,
+        port: u16,
+        ratio: f32,
+        enabled: bool,
+        ids: Vec<u32>,
+        limit: Option<u8>,
+        raw: serde_json::Value,
+    }
+
+    #[test]
+    fn field_types_parse_strings() {
+        let settings = parse_cfg("name 123\nport 7777\nratio 1.5\nenabled true\nids [1, 2]\nlimit 5\nraw 10\n");
+        let typed: Typed = Typed::deserialize(Lenient(Value::Object(settings))).unwrap();
+
+        assert_eq!(
+            typed,
+            Typed {
+                name: "123".to_owned(),
+                port: 7777,
+                ratio: 1.5,
+                enabled: true,
+                ids: vec![1, 2],
+                limit: Some(5),
+                raw: json!("10"),
+            }
+        );
+    }
+
+    #[test]
+    fn string_field_with_number() {
+        let dir = std::env::temp_dir().join(format!("samp-config-number-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("numbers.cfg"), "motd 42\n").unwrap();
+
+        #[derive(Deserialize)]
+        struct Motd {
+            motd: String,
+        }
+
+        let settings: Motd = Config::new("numbers").dir(&dir).env_prefix(None).load().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.motd, "42");
+    }
+
+    #[test]
+    fn invalid_number_is_an_error() {
+        let settings = parse_cfg("port 77x\n");
+        let result = HashMap::<String, u16>::deserialize(Lenient(Value::Object(settings)));
+
+        assert!(result.is_err());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Settings {
+        port: u16,
+        motd: String,
+        db: Db,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Db {
+        host: String,
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let dir = std::env::temp_dir().join(format!("samp-config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("envtest.cfg"), "port 7777\nmotd hello\ndb.host localhost\n").unwrap();
+
+        // names are unique to this test, other tests don't read them
+        std::env::set_var("SAMP_CONFIG_TEST_MOTD", "42");
+        std::env::set_var("SAMP_CONFIG_TEST_DB__HOST", "db.local");
+
+        let config = Config::new("envtest").dir(&dir);
+        let overridden: Settings = config.clone().env_prefix(Some("SAMP_CONFIG_TEST")).load().unwrap();
+        let file_only: Settings = config.env_prefix(None).load().unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            overridden,
+            Settings {
+                port: 7777,
+                motd: "42".to_owned(),
+                db: Db {
+                    host: "db.local".to_owned(),
+                },
+            }
+        );
+        assert_eq!(file_only.motd, "hello");
+        assert_eq!(file_only.db.host, "localhost");
+    }
+}
@@ -1,47 +1,102 @@
+use crate::amx::AmxIdent;
+use crate::plugin::SampPlugin;
 use crate::runtime::Runtime;
-use samp_sdk::raw::types::{AMX, AMX_NATIVE_INFO};
+use samp_sdk::raw::types::AMX;
 
 pub fn supports() -> u32 {
-    let rt = Runtime::get();
-    let supports = rt.supports();
-
-    supports.bits()
+    match Runtime::get() {
+        Ok(rt) => rt.supports().bits(),
+        Err(_) => 0,
+    }
 }
 
-pub fn load(server_exports: *const usize) {
-    let rt = Runtime::get();
-    let plugin = Runtime::plugin();
+/// Return `false` when the plugin can't be created or loaded, the server reports it as failed then.
+pub fn load(server_exports: *const usize) -> bool {
+    let rt = match Runtime::get() {
+        Ok(rt) => rt,
+        Err(_) => return false,
+    };
 
     rt.set_server_exports(server_exports);
-    plugin.on_load();
+
+    let result = match rt.take_load_error() {
+        Some(err) => Err(format!("can't create the plugin: {}", err)),
+        None => match rt.plugin("Load") {
+            Ok(mut plugin) => plugin.on_load().map_err(|err| format!("can't load the plugin: {}", err)),
+            Err(err) => Err(format!("can't call Load: {}", err)),
+        },
+    };
+
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            rt.log(format_args!("error: {}.", err));
+
+            // the server may not call `Unload` for a failed plugin
+            drop(rt.take_plugin());
+            unsafe { Runtime::destroy() };
+
+            false
+        }
+    }
 }
 
 pub fn unload() {
-    let plugin = Runtime::plugin();
-    plugin.on_unload();
+    let rt = match Runtime::get() {
+        Ok(rt) => rt,
+        Err(_) => return,
+    };
+
+    with_plugin(rt, "Unload", |plugin| plugin.on_unload());
+
+    // the plugin is dropped while the runtime is alive, so its `Drop` still can log
+    drop(rt.take_plugin());
+
+    // the server never calls the plugin after `Unload`
+    unsafe { Runtime::destroy() };
 }
 
-pub fn amx_load(amx: *mut AMX, natives: &[AMX_NATIVE_INFO]) {
-    let rt = Runtime::get();
-    let plugin = Runtime::plugin();
+pub fn amx_load(amx: *mut AMX) {
+    let rt = match Runtime::get() {
+        Ok(rt) => rt,
+        Err(_) => return,
+    };
 
-    let amx = rt.insert_amx(amx).unwrap();
-    let _ = amx.register(natives); // don't care about errors, that function always raises errors.
+    let amx = rt.insert_amx(amx);
+    let _ = amx.register(rt.natives()); // don't care about errors, that function always raises errors.
 
-    plugin.on_amx_load(amx);
+    with_plugin(rt, "AmxLoad", |plugin| plugin.on_amx_load(amx));
 }
 
 pub fn amx_unload(amx: *mut AMX) {
-    let rt = Runtime::get();
-    let plugin = Runtime::plugin();
+    let rt = match Runtime::get() {
+        Ok(rt) => rt,
+        Err(_) => return,
+    };
 
     if let Some(amx) = rt.remove_amx(amx) {
-        plugin.on_amx_unload(&amx);
+        with_plugin(rt, "AmxUnload", |plugin| plugin.on_amx_unload(&amx));
+
+        // values of `AmxMap`s are dropped after the plugin has seen the script
+        rt.run_unload_hooks(AmxIdent::from(amx.amx().as_ptr()));
     }
 }
 
 #[inline]
 pub fn process_tick() {
-    let plugin = Runtime::plugin();
-    plugin.process_tick();
+    if let Ok(rt) = Runtime::get() {
+        with_plugin(rt, "ProcessTick", |plugin| plugin.process_tick());
+    }
+}
+
+// a callback can be called by the server inside of a native (e.g. `AmxLoad` from `SendRconCommand("loadfs ...")`),
+// the plugin is borrowed by the native then, so the callback is skipped
+fn with_plugin<F>(rt: &Runtime, callback: &'static str, f: F)
+where
+    F: FnOnce(&mut dyn SampPlugin),
+{
+    match rt.plugin(callback) {
+        Ok(mut plugin) => f(&mut *plugin),
+        Err(err) => rt.log(format_args!("error: can't call {}: {}.", callback, err)),
+    }
 }
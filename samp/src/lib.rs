@@ -20,19 +20,21 @@
 //! # examples
 //! * simple memcache plugin in `plugin-example` folder.
 //! * your `lib.rs` file
-//! ```rust,compile_fail
+//! ```rust,no_run
 //! use samp::prelude::*; // export most useful types
-//! use samp::{native, initialize_plugin}; // codegen macros
+//! use samp::{native, natives, initialize_plugin}; // codegen macros
 //!
 //! struct Plugin;
 //!
 //! impl SampPlugin for Plugin {
 //!     // this function executed when samp server loads your plugin
-//!     fn on_load(&mut self) {
+//!     fn on_load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
 //!         println!("Plugin is loaded.");
+//!         Ok(()) // an error makes the server report the plugin as failed
 //!     }
 //! }
 //!
+//! #[natives] // register all natives of this impl block
 //! impl Plugin {
 //!     #[native(name = "TestNative")]
 //!     fn my_native(&mut self, _amx: &Amx, text: AmxString) -> AmxResult<bool> {
@@ -43,32 +45,68 @@
 //!     }
 //! }
 //!
-//! initialize_plugin!(
-//!     natives: [Plugin::my_native],
-//!     {
-//!         let plugin = Plugin; // create a plugin object
-//!         return plugin; // return the plugin into runtime
+//! initialize_plugin!({
+//!     let plugin = Plugin; // create a plugin object
+//!     return plugin; // return the plugin into runtime
+//! });
+//! ```
+//!
+//! # re-entrancy
+//! A native that calls a public can be called again from that public. Natives taking `&mut self` aren't allowed
+//! to nest, a nested call fails with `AmxError::Native`. Natives that should nest take `&self` and keep
+//! their state in `Cell` or `RefCell`:
+//! ```rust,no_run
+//! use samp::prelude::*;
+//! use samp::{exec_public, native, natives};
+//!
+//! use std::cell::Cell;
+//!
+//! struct Plugin {
+//!     depth: Cell<u32>,
+//! }
+//!
+//! impl SampPlugin for Plugin {}
+//!
+//! #[natives]
+//! impl Plugin {
+//!     // public OnEvent() calls TriggerEvent again
+//!     #[native(name = "TriggerEvent")]
+//!     fn trigger_event(&self, amx: &Amx) -> AmxResult<bool> {
+//!         if self.depth.get() > 4 {
+//!             return Ok(false);
+//!         }
+//!
+//!         self.depth.set(self.depth.get() + 1);
+//!         let result = exec_public!(amx, "OnEvent");
+//!         self.depth.set(self.depth.get() - 1);
+//!
+//!         result.map(|_| true)
 //!     }
-//! );
+//! }
 //! ```
 
 pub mod amx;
+#[cfg(feature = "config")]
+pub mod config;
 #[doc(hidden)]
 pub mod interlayer;
 pub mod plugin;
 pub(crate) mod runtime;
 
-pub use samp_codegen::{initialize_plugin, native};
+pub use samp_codegen::{initialize_plugin, native, natives};
 pub use samp_sdk::{args, cell, consts, error, exports, raw};
 pub use samp_sdk::{exec_public}; // macros
 
 #[cfg(feature = "encoding")]
 pub use samp_sdk::encoding;
 
+#[doc(hidden)]
+pub use linkme;
+
 pub mod prelude {
     //! Most used imports.
     pub use crate::amx::{Amx, AmxExt};
-    pub use crate::cell::{AmxCell, AmxString, Buffer, Ref, UnsizedBuffer};
+    pub use crate::cell::{AmxCell, AmxString, AmxStringMut, AmxStringOut, Buffer, Ref, UnsizedBuffer};
     pub use crate::error::AmxResult;
     pub use crate::plugin::SampPlugin;
 }
@@ -1,28 +1,67 @@
 //! Contains a plugin interface.
-use std::ptr::NonNull;
+use std::cell::{Ref, RefMut};
+use std::error::Error;
+use std::fmt::Display;
+use std::os::raw::c_char;
 
+use linkme::distributed_slice;
 use samp_sdk::amx::Amx;
+use samp_sdk::args::Args;
 use samp_sdk::cell::AmxCell;
+use samp_sdk::error::AmxError;
+use samp_sdk::raw::{functions::AmxNative, types::AMX_NATIVE_INFO};
 
-use crate::runtime::Runtime;
+use crate::runtime::{self, Runtime, RuntimeError};
 
 #[doc(hidden)]
-pub fn initialize<F, T>(constructor: F)
+pub fn initialize<F, T>(constructor: F, natives: Vec<AMX_NATIVE_INFO>)
 where
     F: FnOnce() -> T + 'static,
-    T: SampPlugin + 'static,
+    T: IntoPlugin,
 {
-    let rt = Runtime::initialize();
-    let plugin = constructor();
+    let rt = Runtime::initialize(natives);
+
+    // the server log isn't available before `Load`, so an error is reported there
+    if let Some(name) = rt.duplicated_native() {
+        rt.set_load_error(format!("native {:?} is defined more than once", name));
+    } else {
+        match constructor().into_plugin() {
+            Ok(plugin) => rt.set_plugin(plugin),
+            Err(err) => rt.set_load_error(err),
+        }
+    }
 
-    rt.set_plugin(plugin);
     rt.post_initialize();
 }
 
+/// A value returned by a constructor passed to `initialize_plugin!`: a plugin or `Result` with it.
+#[doc(hidden)]
+pub trait IntoPlugin {
+    type Plugin: SampPlugin + 'static;
+
+    fn into_plugin(self) -> Result<Self::Plugin, String>;
+}
+
+impl<T: SampPlugin + 'static> IntoPlugin for T {
+    type Plugin = T;
+
+    fn into_plugin(self) -> Result<T, String> {
+        Ok(self)
+    }
+}
+
+impl<T: SampPlugin + 'static, E: Display> IntoPlugin for Result<T, E> {
+    type Plugin = T;
+
+    fn into_plugin(self) -> Result<T, String> {
+        self.map_err(|err| err.to_string())
+    }
+}
+
 /// Enables process_tick function for a plugin.
 ///
 /// # Example
-/// ```rust,compile_fail
+/// ```rust,no_run
 /// use samp::initialize_plugin;
 /// use samp::prelude::*;
 ///
@@ -36,8 +75,38 @@ where
 /// });
 /// ```
 pub fn enable_process_tick() {
-    let runtime = Runtime::get();
-    runtime.enable_process_tick();
+    if let Ok(runtime) = Runtime::get() {
+        runtime.enable_process_tick();
+    }
+}
+
+/// Find a native of the plugin by its name, natives are looked up in a sorted table built once when the plugin is loaded.
+///
+/// `None` is returned when there is no such native or when it's called not from the server thread.
+///
+/// # Example
+/// ```rust,no_run
+/// use samp::raw::types::AMX;
+///
+/// fn call_own_native(amx: *mut AMX, args: *mut i32) -> Option<i32> {
+///     let native = samp::plugin::find_native("MyNative")?;
+///     Some(native(amx, args))
+/// }
+/// ```
+pub fn find_native(name: &str) -> Option<AmxNative> {
+    Runtime::get().ok()?.find_native(name)
+}
+
+/// Ask the plugin to reload its settings through [`SampPlugin::reload_config`].
+///
+/// The hook is called right away or, when the plugin is in use by a native, right before the plugin is used next time.
+/// Inside of a native call `self.reload_config()` directly.
+///
+/// [`SampPlugin::reload_config`]: trait.SampPlugin.html#method.reload_config
+pub fn reload_config() {
+    if let Ok(rt) = Runtime::get() {
+        rt.request_reload();
+    }
 }
 
 /// Get a fern [`Dispatch`] and disable auto installing logger.
@@ -84,26 +153,172 @@ pub fn enable_process_tick() {
 /// 
 /// [`Dispatch`]: https://docs.rs/fern/0.5.7/fern/struct.Dispatch.html
 pub fn logger() -> fern::Dispatch {
-    let rt = Runtime::get();
-    rt.disable_default_logger();
+    if let Ok(rt) = Runtime::get() {
+        rt.disable_default_logger();
+    }
 
+    // records from other threads go to stderr, the server log isn't thread-safe
     fern::Dispatch::new()
-        .chain(fern::Output::call(|record| {
-            let rt = Runtime::get();
-            rt.log(record.args());
-        }))
+        .chain(fern::Output::call(|record| runtime::log(record.args())))
+}
+
+/// Get a [`tracing-subscriber`] layer that writes events to the server log.
+///
+/// Every native call is made inside of a `native` span with a name of the native, the AMX and raw argument cells,
+/// and every `Amx::exec` inside of an `exec` span, so events are printed with this context.
+///
+/// # Example
+/// ```rust,no_run
+/// use samp::initialize_plugin;
+/// use samp::prelude::*;
+///
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// struct MyPlugin;
+///
+/// impl SampPlugin for MyPlugin {}
+///
+/// initialize_plugin!({
+///     let subscriber = tracing_subscriber::registry().with(samp::plugin::tracing_layer());
+///     let _ = tracing::subscriber::set_global_default(subscriber);
+///
+///     return MyPlugin;
+/// });
+/// ```
+///
+/// [`tracing-subscriber`]: https://docs.rs/tracing-subscriber/0.3/tracing_subscriber/
+#[cfg(feature = "tracing")]
+pub fn tracing_layer<S>() -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    // the server prints its own timestamp
+    tracing_subscriber::fmt::layer()
+        .with_writer(ServerLog)
+        .without_time()
+}
+
+/// A [`MakeWriter`] that writes lines to the server log, see [`tracing_layer`].
+///
+/// [`MakeWriter`]: https://docs.rs/tracing-subscriber/0.3/tracing_subscriber/fmt/trait.MakeWriter.html
+/// [`tracing_layer`]: fn.tracing_layer.html
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ServerLog;
+
+#[cfg(feature = "tracing")]
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for ServerLog {
+    type Writer = ServerLogLine;
+
+    fn make_writer(&'a self) -> ServerLogLine {
+        ServerLogLine { buf: Vec::new() }
+    }
+}
+
+/// A line of the server log, it's written when dropped.
+#[cfg(feature = "tracing")]
+#[derive(Debug)]
+pub struct ServerLogLine {
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "tracing")]
+impl std::io::Write for ServerLogLine {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for ServerLogLine {
+    fn drop(&mut self) {
+        if self.buf.is_empty() {
+            return;
+        }
+
+        let line = String::from_utf8_lossy(&self.buf);
+        runtime::log(line.trim_end_matches('\n'));
+    }
 }
 
 #[doc(hidden)]
-pub fn get<T: SampPlugin + 'static>() -> NonNull<T> {
-    Runtime::plugin_cast()
+pub struct NativeSpan {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn native_span(name: &'static str, amx: &Amx, args: &Args) -> NativeSpan {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::info_span!(
+            "native",
+            name,
+            amx = ?amx.amx().as_ptr(),
+            args = tracing::field::Empty
+        );
+
+        // reading arguments isn't free, so they're recorded only when someone listens
+        if !span.is_disabled() {
+            let cells: Vec<i32> = (0..args.count()).filter_map(|idx| args.get(idx)).collect();
+            span.record("args", tracing::field::debug(&cells));
+        }
+
+        NativeSpan {
+            _span: span.entered(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (name, amx, args);
+        NativeSpan {}
+    }
+}
+
+/// Borrow the plugin for a native that takes `&mut self`.
+///
+/// `None` is returned when the native is called from another thread or re-entered while the plugin is already borrowed,
+/// the reason is logged and `AmxError::Native` is raised, so a public that has called the native is aborted.
+#[doc(hidden)]
+pub fn get<T: SampPlugin + 'static>(native: &'static str, amx: &Amx) -> Option<RefMut<'static, T>> {
+    let plugin = Runtime::get().and_then(|rt| rt.plugin_cast(native));
+    borrowed(native, amx, plugin)
+}
+
+/// Borrow the plugin for a native that takes `&self`, nested calls of such natives are allowed.
+#[doc(hidden)]
+pub fn get_ref<T: SampPlugin + 'static>(native: &'static str, amx: &Amx) -> Option<Ref<'static, T>> {
+    let plugin = Runtime::get().and_then(|rt| rt.plugin_cast_ref());
+    borrowed(native, amx, plugin)
+}
+
+fn borrowed<P>(native: &'static str, amx: &Amx, plugin: Result<P, RuntimeError>) -> Option<P> {
+    match plugin {
+        Ok(plugin) => Some(plugin),
+        Err(err) => {
+            runtime::log(format_args!("error: can't call native {:?}: {}.", native, err));
+            let _ = amx.raise_error(AmxError::Native);
+            None
+        }
+    }
 }
 
 /// An interface that should be implemented by any plugin.
 ///
 /// All methods are optional
 pub trait SampPlugin {
-    fn on_load(&mut self) {}
+    /// Called when the server loads the plugin, an error is logged and the server reports the plugin as failed.
+    fn on_load(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
     fn on_unload(&mut self) {}
 
     fn on_amx_load(&mut self, amx: &Amx) {
@@ -115,6 +330,70 @@ pub trait SampPlugin {
     }
 
     fn process_tick(&mut self) {}
+
+    /// Called by [`reload_config`](fn.reload_config.html) to read settings again, an error is logged.
+    fn reload_config(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Natives registered by `#[natives]`.
+#[doc(hidden)]
+#[distributed_slice]
+pub static NATIVES: [NativeEntry] = [..];
+
+#[doc(hidden)]
+pub struct NativeEntry {
+    // nul-terminated name of a native
+    name: &'static str,
+    func: AmxNative,
+}
+
+impl NativeEntry {
+    pub const fn new(name: &'static str, func: AmxNative) -> NativeEntry {
+        NativeEntry { name, func }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.name.trim_end_matches('\0')
+    }
+
+    #[inline]
+    pub fn info(&self) -> AMX_NATIVE_INFO {
+        AMX_NATIVE_INFO {
+            name: self.name.as_ptr() as *const c_char,
+            func: self.func,
+        }
+    }
+}
+
+/// Implemented by `initialize_plugin!` for every type with natives in its `natives: [...]` list.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`#[native]` methods of `{Self}` are never registered",
+    label = "`{Self}` has a `#[native]` outside of `#[natives]`",
+    note = "put the impl block under `#[natives]` or list its natives in `initialize_plugin!(natives: [...])`"
+)]
+pub trait ListedNatives {}
+
+#[doc(hidden)]
+#[inline]
+pub fn listed_natives<T: ListedNatives + ?Sized>() {}
+
+#[doc(hidden)]
+pub fn natives() -> Vec<AMX_NATIVE_INFO> {
+    NATIVES.iter().map(NativeEntry::info).collect()
+}
+
+#[doc(hidden)]
+pub fn native_error<T: std::fmt::Display>(message: T) {
+    runtime::log(format_args!("error: {}", message));
+}
+
+#[doc(hidden)]
+pub fn warn_deprecated(old_name: &str, new_name: &str) {
+    runtime::log(format_args!("warning: native {:?} is deprecated, use {:?} instead.", old_name, new_name));
 }
 
 #[doc(hidden)]
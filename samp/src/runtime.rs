@@ -1,44 +1,123 @@
 use samp_sdk::consts::{ServerData, Supports};
-use samp_sdk::raw::{functions::Logprintf, types::AMX};
+use samp_sdk::raw::functions::{AmxNative, Logprintf};
+use samp_sdk::raw::types::{AMX, AMX_NATIVE_INFO};
 
+use std::any::TypeId;
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::collections::HashMap;
-use std::ptr::NonNull;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::ptr;
+use std::rc::Weak;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::OnceLock;
+use std::thread::{self, ThreadId};
 
-use crate::amx::{Amx, AmxIdent};
+use crate::amx::{Amx, AmxIdent, AmxUnloadHook};
 use crate::plugin::SampPlugin;
 
-static mut RUNTIME: *mut Runtime = std::ptr::null_mut();
+static RUNTIME: AtomicPtr<Runtime> = AtomicPtr::new(ptr::null_mut());
 
+// the thread that called `Supports`, the server never calls a plugin from other threads
+static SERVER_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+// the server formats a log line in a 1024 bytes buffer, some space is left for a timestamp
+const MAX_LOG_LINE: usize = 1000;
+
+/// A reason why the runtime or the plugin can't be accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeError {
+    /// The plugin isn't initialized yet.
+    NotInitialized,
+    /// The caller isn't on the server thread.
+    WrongThread,
+    /// The plugin is already borrowed by a native or a callback with this name.
+    Reentrant(&'static str),
+    /// The plugin is borrowed by natives that take `&self`, so it can't be borrowed mutably.
+    Shared,
+    /// The plugin is requested as a type that it doesn't have.
+    PluginType,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::NotInitialized => write!(f, "the plugin isn't initialized"),
+            RuntimeError::WrongThread => write!(f, "called from a thread other than the server thread"),
+            RuntimeError::Reentrant(user) => write!(f, "the plugin is already in use by {}", user),
+            RuntimeError::Shared => write!(f, "the plugin is in use by a native that takes `&self`"),
+            RuntimeError::PluginType => write!(f, "the plugin has another type"),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// State of the plugin, it's accessed only from the server thread.
 pub struct Runtime {
-    plugin: Option<NonNull<dyn SampPlugin + 'static>>,
-    process_tick: bool,
-    server_exports: *const usize,
-    amx_list: HashMap<AmxIdent, Amx>,
-    logger_enabled: bool,
+    plugin: RefCell<Option<Box<dyn SampPlugin + 'static>>>,
+    plugin_type: Cell<Option<TypeId>>,
+    plugin_user: Cell<&'static str>,
+    process_tick: Cell<bool>,
+    server_exports: Cell<*const usize>,
+    amx_list: RefCell<HashMap<AmxIdent, Box<Amx>>>,
+    amx_order: RefCell<Vec<AmxIdent>>,
+    logger_enabled: Cell<bool>,
+    // sorted by name, registered in every loaded script
+    natives: Vec<AMX_NATIVE_INFO>,
+    load_error: RefCell<Option<String>>,
+    reload_pending: Cell<bool>,
+    unload_hooks: RefCell<Vec<Weak<dyn AmxUnloadHook>>>,
 }
 
 impl Runtime {
-    pub fn initialize() -> &'static mut Runtime {
+    pub fn initialize(mut natives: Vec<AMX_NATIVE_INFO>) -> &'static Runtime {
+        SERVER_THREAD.get_or_init(|| thread::current().id());
+
+        natives.sort_by(|a, b| unsafe { native_name(a).cmp(native_name(b)) });
+
         let rt = Runtime {
-            plugin: None,
-            process_tick: false,
-            server_exports: std::ptr::null(),
-            amx_list: HashMap::default(),
-            logger_enabled: true,
+            plugin: RefCell::new(None),
+            plugin_type: Cell::new(None),
+            plugin_user: Cell::new(""),
+            process_tick: Cell::new(false),
+            server_exports: Cell::new(ptr::null()),
+            amx_list: RefCell::new(HashMap::default()),
+            amx_order: RefCell::new(Vec::new()),
+            logger_enabled: Cell::new(true),
+            natives,
+            load_error: RefCell::new(None),
+            reload_pending: Cell::new(false),
+            unload_hooks: RefCell::new(Vec::new()),
         };
 
-        let boxed = Box::new(rt);
+        let boxed = Box::into_raw(Box::new(rt));
+        let previous = RUNTIME.swap(boxed, Ordering::AcqRel);
 
-        unsafe {
-            RUNTIME = Box::into_raw(boxed);
+        // the server calls `Supports` once, but a previous runtime isn't leaked if it's called again
+        if !previous.is_null() {
+            unsafe { drop(Box::from_raw(previous)) };
         }
 
-        Runtime::get()
+        // the pointer is made from a box right above
+        unsafe { &*boxed }
+    }
+
+    /// Drop the runtime, so a plugin loaded again by the server starts clean.
+    ///
+    /// # Safety
+    /// It should be called on the server thread when nothing refers to the runtime or an `Amx` from it,
+    /// that is when the server unloads the plugin.
+    pub unsafe fn destroy() {
+        let rt = RUNTIME.swap(ptr::null_mut(), Ordering::AcqRel);
+
+        if !rt.is_null() {
+            drop(Box::from_raw(rt));
+        }
     }
 
     pub fn post_initialize(&self) {
-        if !self.logger_enabled {
+        if !self.logger_enabled.get() {
             return;
         }
 
@@ -50,6 +129,7 @@ impl Runtime {
     pub fn amx_exports(&self) -> usize {
         unsafe {
             self.server_exports
+                .get()
                 .offset(ServerData::AmxExports.into())
                 .read()
         }
@@ -58,81 +138,290 @@ impl Runtime {
     #[inline]
     pub fn logger(&self) -> Logprintf {
         unsafe {
-            (self.server_exports.offset(ServerData::Logprintf.into()) as *const Logprintf).read()
+            (self.server_exports.get().offset(ServerData::Logprintf.into()) as *const Logprintf).read()
         }
     }
-    
-    pub fn disable_default_logger(&mut self) {
-        self.logger_enabled = false;
+
+    pub fn disable_default_logger(&self) {
+        self.logger_enabled.set(false);
     }
 
-    pub fn log<T: std::fmt::Display>(&self, message: T) {
+    /// Write a message to the server log, a long message is split into several lines.
+    pub fn log<T: fmt::Display>(&self, message: T) {
+        let message = message.to_string();
+        let mut rest = message.as_str();
+
+        loop {
+            let mut end = rest.len().min(MAX_LOG_LINE);
+
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let (line, tail) = rest.split_at(end);
+            self.log_line(line);
+
+            if tail.is_empty() {
+                break;
+            }
+
+            rest = tail;
+        }
+    }
+
+    // a message is never passed as a format string, `%` in player input would be parsed by the server's printf
+    fn log_line(&self, line: &str) {
+        // the server passes its functions only in `Load`
+        if self.server_exports.get().is_null() {
+            eprintln!("{}", line);
+            return;
+        }
+
         let log_fn = self.logger();
-        let msg = format!("{}", message);
-        
-        match CString::new(msg) {
-            Ok(cstr) => log_fn(cstr.as_ptr()),
-            Err(_) => (),
+
+        let line = if line.contains('\0') {
+            CString::new(line.replace('\0', ""))
+        } else {
+            CString::new(line)
+        };
+
+        if let Ok(cstr) = line {
+            log_fn(b"%s\0".as_ptr() as *const i8, cstr.as_ptr());
         }
     }
 
-    pub fn insert_amx(&mut self, amx: *mut AMX) -> Option<&Amx> {
+    pub fn insert_amx(&self, amx: *mut AMX) -> &Amx {
         let ident = AmxIdent::from(amx);
-        let amx = Amx::new(amx, self.amx_exports());
+        let amx = Box::new(Amx::new(amx, self.amx_exports()));
+        let amx_ref = unsafe { Runtime::amx_ref(&amx) };
+
+        if self.amx_list.borrow_mut().insert(ident, amx).is_none() {
+            self.amx_order.borrow_mut().push(ident);
+        }
 
-        self.amx_list.insert(ident, amx);
-        self.amx_list.get(&ident)
+        amx_ref
     }
 
-    pub fn remove_amx(&mut self, amx: *mut AMX) -> Option<Amx> {
+    pub fn remove_amx(&self, amx: *mut AMX) -> Option<Box<Amx>> {
         let ident = AmxIdent::from(amx);
-        self.amx_list.remove(&ident)
+        self.amx_order.borrow_mut().retain(|&loaded| loaded != ident);
+        self.amx_list.borrow_mut().remove(&ident)
+    }
+
+    /// Return all loaded scripts in load order.
+    pub fn all_amx(&self) -> Vec<&Amx> {
+        let amx_list = self.amx_list.borrow();
+
+        self.amx_order
+            .borrow()
+            .iter()
+            .filter_map(|ident| amx_list.get(ident))
+            .map(|amx| unsafe { Runtime::amx_ref(amx) })
+            .collect()
+    }
+
+    pub fn register_unload_hook(&self, hook: Weak<dyn AmxUnloadHook>) {
+        self.unload_hooks.borrow_mut().push(hook);
+    }
+
+    /// Notify `AmxMap`s that a script is unloaded, hooks of dropped maps are removed.
+    pub fn run_unload_hooks(&self, ident: AmxIdent) {
+        let hooks: Vec<_> = {
+            let mut unload_hooks = self.unload_hooks.borrow_mut();
+            unload_hooks.retain(|hook| hook.strong_count() > 0);
+            unload_hooks.iter().filter_map(Weak::upgrade).collect()
+        };
+
+        // the list isn't borrowed here, a dropped value can create another map
+        for hook in hooks {
+            hook.amx_unloaded(ident);
+        }
+    }
+
+    pub fn get_amx(&self, ident: AmxIdent) -> Option<&Amx> {
+        let amx_list = self.amx_list.borrow();
+        amx_list.get(&ident).map(|amx| unsafe { Runtime::amx_ref(amx) })
+    }
+
+    // an `Amx` is boxed, so it stays in place when the list is changed by a nested callback,
+    // it's dropped only when the server unloads the script
+    unsafe fn amx_ref<'a>(amx: &Amx) -> &'a Amx {
+        &*(amx as *const Amx)
+    }
+
+    #[inline]
+    pub fn natives(&self) -> &[AMX_NATIVE_INFO] {
+        &self.natives
+    }
+
+    /// Find a native of the plugin by its name.
+    pub fn find_native(&self, name: &str) -> Option<AmxNative> {
+        let idx = self
+            .natives
+            .binary_search_by(|native| unsafe { native_name(native) }.to_bytes().cmp(name.as_bytes()))
+            .ok()?;
+
+        Some(self.natives[idx].func)
+    }
+
+    /// A name registered by more than one native, natives in different modules aren't checked at compile time.
+    pub fn duplicated_native(&self) -> Option<&'static str> {
+        self.natives
+            .windows(2)
+            .map(|pair| unsafe { (native_name(&pair[0]), native_name(&pair[1])) })
+            .find(|(a, b)| a == b)
+            .map(|(name, _)| name.to_str().unwrap_or("?"))
     }
 
     pub fn supports(&self) -> Supports {
         let mut supports = Supports::VERSION | Supports::AMX_NATIVES;
 
-        if self.process_tick {
+        if self.process_tick.get() {
             supports.toggle(Supports::PROCESS_TICK);
         }
 
         supports
     }
 
-    #[inline]
-    pub fn amx_list(&self) -> &HashMap<AmxIdent, Amx> {
-        &self.amx_list
-    }
-
-    pub fn set_plugin<T>(&mut self, plugin: T)
+    pub fn set_plugin<T>(&self, plugin: T)
     where
         T: SampPlugin + 'static,
     {
-        let boxed = Box::new(plugin);
-        self.plugin = NonNull::new(Box::into_raw(boxed));
+        *self.plugin.borrow_mut() = Some(Box::new(plugin));
+        self.plugin_type.set(Some(TypeId::of::<T>()));
     }
 
-    pub fn set_server_exports(&mut self, exports: *const usize) {
-        self.server_exports = exports;
+    /// Take the plugin out of the runtime, `None` when it's borrowed or already taken.
+    pub fn take_plugin(&self) -> Option<Box<dyn SampPlugin>> {
+        let mut plugin = self.plugin.try_borrow_mut().ok()?;
+
+        self.plugin_type.set(None);
+        plugin.take()
     }
 
-    pub fn enable_process_tick(&mut self) {
-        self.process_tick = true;
+    /// Keep an error returned by the plugin constructor until `Load`.
+    pub fn set_load_error(&self, error: String) {
+        *self.load_error.borrow_mut() = Some(error);
     }
 
-    #[inline]
-    pub fn get() -> &'static mut Runtime {
-        unsafe { &mut *RUNTIME }
+    pub fn take_load_error(&self) -> Option<String> {
+        self.load_error.borrow_mut().take()
     }
 
-    #[inline]
-    pub fn plugin() -> &'static mut dyn SampPlugin {
-        unsafe { (*RUNTIME).plugin.as_mut().unwrap().as_mut() }
+    pub fn set_server_exports(&self, exports: *const usize) {
+        self.server_exports.set(exports);
     }
 
+    pub fn enable_process_tick(&self) {
+        self.process_tick.set(true);
+    }
+
+    /// Get the runtime, fails when it isn't initialized or when it's called not from the server thread.
     #[inline]
-    pub fn plugin_cast<T: SampPlugin>() -> NonNull<T> {
-        let rt = Runtime::get();
-        rt.plugin.as_ref().unwrap().cast()
+    pub fn get() -> Result<&'static Runtime, RuntimeError> {
+        if SERVER_THREAD.get() != Some(&thread::current().id()) {
+            return match SERVER_THREAD.get() {
+                Some(_) => Err(RuntimeError::WrongThread),
+                None => Err(RuntimeError::NotInitialized),
+            };
+        }
+
+        let rt = RUNTIME.load(Ordering::Acquire);
+
+        // the runtime is created and used only on the server thread
+        unsafe { rt.as_ref().ok_or(RuntimeError::NotInitialized) }
+    }
+
+    /// Borrow the plugin mutably, `user` is a name of a native or a callback that is reported on re-entrancy.
+    pub fn plugin(&self, user: &'static str) -> Result<RefMut<'_, dyn SampPlugin>, RuntimeError> {
+        let plugin = self.plugin.try_borrow_mut().map_err(|_| self.borrow_error())?;
+
+        let mut plugin = RefMut::filter_map(plugin, |plugin| plugin.as_deref_mut())
+            .map_err(|_| RuntimeError::NotInitialized)?;
+
+        self.plugin_user.set(user);
+
+        if self.reload_pending.take() {
+            self.reload(&mut *plugin);
+        }
+
+        Ok(plugin)
+    }
+
+    /// Reload settings of the plugin now, or when it's borrowed next time if it's in use.
+    pub fn request_reload(&self) {
+        self.reload_pending.set(true);
+
+        // the borrow runs the reload if the plugin isn't in use
+        let _ = self.plugin("reload_config");
+    }
+
+    fn reload(&self, plugin: &mut dyn SampPlugin) {
+        if let Err(err) = plugin.reload_config() {
+            self.log(format_args!("error: can't reload the config: {}.", err));
+        }
+    }
+
+    /// Borrow the plugin mutably as its concrete type.
+    pub fn plugin_cast<T: SampPlugin + 'static>(&self, user: &'static str) -> Result<RefMut<'_, T>, RuntimeError> {
+        self.check_type::<T>()?;
+
+        let plugin = self.plugin(user)?;
+
+        // the type is checked above
+        Ok(RefMut::map(plugin, |plugin| unsafe {
+            &mut *(plugin as *mut dyn SampPlugin as *mut T)
+        }))
+    }
+
+    /// Borrow the plugin as its concrete type for a native that takes `&self`,
+    /// such borrows can be nested but not with a mutable one.
+    pub fn plugin_cast_ref<T: SampPlugin + 'static>(&self) -> Result<Ref<'_, T>, RuntimeError> {
+        self.check_type::<T>()?;
+
+        // a pending reload needs a mutable borrow, it's skipped when natives are nested
+        if self.reload_pending.get() {
+            let _ = self.plugin("reload_config");
+        }
+
+        let plugin = self.plugin.try_borrow().map_err(|_| self.borrow_error())?;
+
+        let plugin = Ref::filter_map(plugin, |plugin| plugin.as_deref())
+            .map_err(|_| RuntimeError::NotInitialized)?;
+
+        // the type is checked above
+        Ok(Ref::map(plugin, |plugin| unsafe {
+            &*(plugin as *const dyn SampPlugin as *const T)
+        }))
+    }
+
+    fn check_type<T: SampPlugin + 'static>(&self) -> Result<(), RuntimeError> {
+        if self.plugin_type.get() != Some(TypeId::of::<T>()) {
+            return Err(RuntimeError::PluginType);
+        }
+
+        Ok(())
+    }
+
+    // `plugin_user` is set only by mutable borrows, so it's known only when the plugin isn't shared
+    fn borrow_error(&self) -> RuntimeError {
+        if self.plugin.try_borrow().is_ok() {
+            RuntimeError::Shared
+        } else {
+            RuntimeError::Reentrant(self.plugin_user.get())
+        }
+    }
+}
+
+// names of natives are static nul-terminated strings made by `#[native]` and `#[natives]`
+unsafe fn native_name(native: &AMX_NATIVE_INFO) -> &'static CStr {
+    CStr::from_ptr(native.name)
+}
+
+/// Write a message to the server log, or to stderr when it's called not from the server thread.
+pub fn log<T: fmt::Display>(message: T) {
+    match Runtime::get() {
+        Ok(rt) => rt.log(message),
+        Err(_) => eprintln!("{}", message),
     }
 }